@@ -0,0 +1,57 @@
+// Command-line arguments. Kept dependency-free: the handful of flags doesn't warrant a parser
+// crate.
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: rpi_emulator_frontend [OPTIONS] [ROMS_DIR]
+
+Options:
+  --roms <path>    ROM root folder (same as the ROMS_DIR argument)
+  --config <path>  Use this config file instead of ~/.config/rpi_emulator_frontend/config.toml
+                   (overrides the RPI_FRONTEND_CONFIG environment variable)
+  --list           Print the systems and ROMs that would be shown, and folders that have
+                   files but no [systems] entry, then exit
+  --reset-config   Rename the config and style files to *.toml.bak (*.toml.bak.1, ... if
+                   that exists), write fresh defaults, then exit
+  -h, --help       Print this help
+  -V, --version    Print the version";
+
+#[derive(Debug, Default, PartialEq)]
+pub struct CliArgs {
+    pub roms: Option<String>,
+    pub config: Option<PathBuf>,
+    pub list: bool,
+    pub reset_config: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum CliAction {
+    Run(CliArgs),
+    Help,
+    Version,
+}
+
+// Parse the arguments after the program name.
+pub fn parse_args(args: impl IntoIterator<Item = String>) -> Result<CliAction, String> {
+    let mut parsed = CliArgs::default();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(CliAction::Help),
+            "-V" | "--version" => return Ok(CliAction::Version),
+            "--list" => parsed.list = true,
+            "--reset-config" => parsed.reset_config = true,
+            "--roms" => {
+                parsed.roms = Some(args.next().ok_or("--roms needs a path")?);
+            }
+            "--config" => {
+                parsed.config = Some(PathBuf::from(args.next().ok_or("--config needs a path")?));
+            }
+            s if s.starts_with('-') => return Err(format!("unknown option: {}", s)),
+            // bare positional roms dir, as accepted before options existed
+            _ if parsed.roms.is_none() => parsed.roms = Some(arg),
+            _ => return Err(format!("unexpected argument: {}", arg)),
+        }
+    }
+    Ok(CliAction::Run(parsed))
+}
@@ -0,0 +1,133 @@
+// Optional control interface on a Unix domain socket, for driving the UI from other programs
+// (e.g. a web remote). Clients send one command per line and get one reply per command:
+//   list            -> "system\t<name>", "<index>\t<file name>" per ROM, then "end"
+//   select <index>  -> "ok" or "error: ..."
+//   launch          -> "ok" or "error: ..."
+//   kill            -> status message
+//   next-system     -> "ok"
+// Commands are handed to the main loop over a channel, like the X11 kill hotkey.
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, PartialEq)]
+pub enum ControlCommand {
+    List,
+    Select(usize),
+    Launch,
+    Kill,
+    NextSystem,
+}
+
+// A command plus where to send its reply (the reply may span several lines).
+pub struct ControlRequest {
+    pub cmd: ControlCommand,
+    pub reply: mpsc::Sender<String>,
+}
+
+pub fn parse_command(line: &str) -> Result<ControlCommand, String> {
+    let mut parts = line.split_whitespace();
+    let cmd = match parts.next() {
+        Some("list") => ControlCommand::List,
+        Some("select") => match parts.next().map(|n| n.parse::<usize>()) {
+            Some(Ok(i)) => ControlCommand::Select(i),
+            _ => return Err("usage: select <index>".to_string()),
+        },
+        Some("launch") => ControlCommand::Launch,
+        Some("kill") => ControlCommand::Kill,
+        Some("next-system") => ControlCommand::NextSystem,
+        Some(other) => return Err(format!("unknown command: {}", other)),
+        None => return Err("empty command".to_string()),
+    };
+    if parts.next().is_some() {
+        return Err("too many arguments".to_string());
+    }
+    Ok(cmd)
+}
+
+// Listen on `path` (replacing a stale socket file) and forward client commands to `tx`.
+// Each client is served on its own thread until it disconnects. Anything at `path` that isn't a
+// socket is left alone and reported as an error, so a typo in control_socket can't delete a file.
+pub fn listen(path: &Path, tx: mpsc::Sender<ControlRequest>) -> std::io::Result<()> {
+    if let Ok(meta) = std::fs::symlink_metadata(path) {
+        if !meta.file_type().is_socket() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                "path exists and is not a socket",
+            ));
+        }
+        std::fs::remove_file(path)?;
+    }
+    let listener = UnixListener::bind(path)?;
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let tx = tx.clone();
+                    thread::spawn(move || serve_client(stream, tx));
+                }
+                Err(e) => eprintln!("Control socket accept failed: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+fn serve_client(stream: UnixStream, tx: mpsc::Sender<ControlRequest>) {
+    let mut writer = match stream.try_clone() {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Control socket client error: {}", e);
+            return;
+        }
+    };
+    // a read error or EOF means the client went away; just drop the connection
+    for line in BufReader::new(stream).lines() {
+        let Ok(line) = line else { break };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let reply = match parse_command(&line) {
+            Ok(cmd) => {
+                let (reply_tx, reply_rx) = mpsc::channel();
+                if tx
+                    .send(ControlRequest {
+                        cmd,
+                        reply: reply_tx,
+                    })
+                    .is_err()
+                {
+                    // the UI has shut down
+                    return;
+                }
+                reply_rx
+                    .recv_timeout(Duration::from_secs(2))
+                    .unwrap_or_else(|_| "error: no response".to_string())
+            }
+            Err(e) => format!("error: {}", e),
+        };
+        if writeln!(writer, "{}", reply).is_err() {
+            break;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn listen_leaves_other_files_alone() {
+        let path =
+            std::env::temp_dir().join(format!("rpi_frontend_control_{}", std::process::id()));
+        std::fs::write(&path, "not a socket").unwrap();
+        let (tx, _rx) = mpsc::channel();
+        assert!(listen(&path, tx).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "not a socket");
+        std::fs::remove_file(&path).unwrap();
+    }
+}
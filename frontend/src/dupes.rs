@@ -0,0 +1,194 @@
+// Duplicate-ROM report for the "Find duplicates" menu entry: ROMs whose names (without extension)
+// match, across formats, folders and systems. With hashing on, each set also says whether the
+// files are byte-for-byte identical.
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+
+pub struct DuplicateSet {
+    pub name: String,
+    pub files: Vec<PathBuf>,
+    // Some(true) if every file has the same contents; None when not checked
+    pub identical: Option<bool>,
+}
+
+// Sets of ROMs in `groups` sharing a name (compared case-insensitively), sorted by name.
+pub fn find(groups: &HashMap<String, Vec<PathBuf>>, hash: bool) -> Vec<DuplicateSet> {
+    let mut by_stem: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    for p in groups.values().flatten() {
+        let stem = p
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_lowercase();
+        by_stem.entry(stem).or_default().push(p.clone());
+    }
+    let mut sets: Vec<DuplicateSet> = by_stem
+        .into_values()
+        .filter(|files| files.len() > 1)
+        .map(|mut files| {
+            files.sort();
+            let name = files[0]
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            let identical = if hash {
+                Some(same_contents(&files))
+            } else {
+                None
+            };
+            DuplicateSet {
+                name,
+                files,
+                identical,
+            }
+        })
+        .collect();
+    sets.sort_by(|a, b| crate::text::natural_cmp(&a.name, &b.name));
+    sets
+}
+
+// Run `find` on a background thread, since hashing reads every duplicate in full. The sets are
+// sent on `done` when it finishes.
+pub fn find_in_background(
+    groups: HashMap<String, Vec<PathBuf>>,
+    hash: bool,
+    done: mpsc::Sender<Vec<DuplicateSet>>,
+) {
+    thread::spawn(move || {
+        let _ = done.send(find(&groups, hash));
+    });
+}
+
+// True if all `files` have the same size and hash. Sizes are compared first so differing files
+// are usually told apart without reading them.
+fn same_contents(files: &[PathBuf]) -> bool {
+    let sizes: Vec<Option<u64>> = files
+        .iter()
+        .map(|f| std::fs::metadata(f).ok().map(|m| m.len()))
+        .collect();
+    if sizes.iter().any(Option::is_none) || sizes.windows(2).any(|s| s[0] != s[1]) {
+        return false;
+    }
+    let first = match hash_file(&files[0]) {
+        Some(h) => h,
+        None => return false,
+    };
+    files[1..].iter().all(|f| hash_file(f) == Some(first))
+}
+
+// 64-bit FNV-1a of the file's contents: fast, and good enough to confirm a name match.
+fn hash_file(path: &Path) -> Option<u64> {
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325;
+    loop {
+        let n = file.read(&mut buf).ok()?;
+        if n == 0 {
+            return Some(h);
+        }
+        for b in &buf[..n] {
+            h ^= u64::from(*b);
+            h = h.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+}
+
+// The report as display lines: a heading per set, then each file relative to `roms_dir`.
+pub fn report_lines(sets: &[DuplicateSet], roms_dir: &Path) -> Vec<String> {
+    if sets.is_empty() {
+        return vec!["No duplicates found".to_string()];
+    }
+    let mut lines = Vec::new();
+    for set in sets {
+        let note = match set.identical {
+            Some(true) => ", identical",
+            Some(false) => ", contents differ",
+            None => "",
+        };
+        lines.push(format!("{} ({} files{})", set.name, set.files.len(), note));
+        for f in &set.files {
+            let shown = f.strip_prefix(roms_dir).unwrap_or(f);
+            lines.push(format!("  {}", shown.display()));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn groups(entries: &[(&str, &[&str])]) -> HashMap<String, Vec<PathBuf>> {
+        entries
+            .iter()
+            .map(|(sys, files)| (sys.to_string(), files.iter().map(PathBuf::from).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn sets_match_names_across_systems_and_extensions() {
+        let g = groups(&[
+            ("nes", &["/roms/nes/Zelda.nes", "/roms/nes/Metroid.nes"]),
+            ("fds", &["/roms/fds/zelda.fds"]),
+        ]);
+        let sets = find(&g, false);
+        assert_eq!(sets.len(), 1);
+        assert_eq!(sets[0].name, "zelda");
+        assert_eq!(
+            sets[0].files,
+            vec![
+                PathBuf::from("/roms/fds/zelda.fds"),
+                PathBuf::from("/roms/nes/Zelda.nes")
+            ]
+        );
+        assert_eq!(sets[0].identical, None);
+    }
+
+    #[test]
+    fn sets_are_sorted_naturally() {
+        let g = groups(&[
+            ("a", &["/a/Game 10.a", "/a/Game 2.a", "/a/Game 1.a"]),
+            ("b", &["/b/Game 10.b", "/b/Game 2.b", "/b/Game 1.b"]),
+        ]);
+        let names: Vec<String> = find(&g, false).into_iter().map(|s| s.name).collect();
+        assert_eq!(names, ["Game 1", "Game 2", "Game 10"]);
+    }
+
+    #[test]
+    fn hashing_tells_identical_sets_from_differing_ones() {
+        let dir = std::env::temp_dir().join(format!("rpi_frontend_dupes_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, data: &[u8]| {
+            let p = dir.join(name);
+            std::fs::write(&p, data).unwrap();
+            p.to_string_lossy().into_owned()
+        };
+        let same = [write("same.a", b"rom"), write("same.b", b"rom")];
+        let differ = [write("differ.a", b"rom1"), write("differ.b", b"rom2")];
+        let sized = [write("sized.a", b"rom"), write("sized.b", b"longer rom")];
+        let all: Vec<&str> = same
+            .iter()
+            .chain(&differ)
+            .chain(&sized)
+            .map(|s| s.as_str())
+            .collect();
+        let sets = find(&groups(&[("sys", &all)]), true);
+        std::fs::remove_dir_all(&dir).unwrap();
+        let identical: Vec<(&str, Option<bool>)> = sets
+            .iter()
+            .map(|s| (s.name.as_str(), s.identical))
+            .collect();
+        assert_eq!(
+            identical,
+            [
+                ("differ", Some(false)),
+                ("same", Some(true)),
+                ("sized", Some(false))
+            ]
+        );
+    }
+}
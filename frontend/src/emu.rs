@@ -0,0 +1,478 @@
+use crate::CmdTemplate;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// Resolve `program` like a shell would: anything containing a path separator is checked as-is,
+// bare names are looked up in each PATH entry. Returns the first executable match.
+pub fn which(program: &str) -> Option<PathBuf> {
+    let p = Path::new(program);
+    if p.is_absolute() || p.components().count() > 1 {
+        return if is_executable(p) {
+            Some(p.to_path_buf())
+        } else {
+            None
+        };
+    }
+    let path = std::env::var_os("PATH")?;
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(program))
+        .find(|candidate| is_executable(candidate))
+}
+
+#[cfg(unix)]
+fn is_executable(p: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    p.metadata()
+        .map(|m| m.is_file() && m.permissions().mode() & 0o111 != 0)
+        .unwrap_or(false)
+}
+
+#[cfg(not(unix))]
+fn is_executable(p: &Path) -> bool {
+    p.is_file()
+}
+
+// Whether `tmpl`'s program can't be found. Shell templates are left to the shell to resolve,
+// since their "program" may be any shell word.
+pub fn program_missing(tmpl: &CmdTemplate) -> bool {
+    !tmpl.shell.unwrap_or(false) && which(&tmpl.program).is_none()
+}
+
+// User-facing message for a program that `which` could not resolve.
+pub fn not_found_message(program: &str) -> String {
+    if Path::new(program).components().count() > 1 {
+        format!("{} not found", program)
+    } else {
+        format!("{} not found in PATH", program)
+    }
+}
+
+// The template's arguments before substitution. With `launcher = "retroarch"` and a `core`, they
+// are `-L <core>` followed by `args` (just `{rom}` if empty), as RetroArch expects.
+fn template_args(tmpl: &CmdTemplate) -> Vec<String> {
+    match (tmpl.launcher.as_deref(), tmpl.core.as_deref()) {
+        (Some("retroarch"), Some(core)) => {
+            let mut args = vec!["-L".to_string(), crate::paths::expand_path(core)];
+            if tmpl.args.is_empty() {
+                args.push("{rom}".to_string());
+            } else {
+                args.extend(tmpl.args.iter().cloned());
+            }
+            args
+        }
+        _ => tmpl.args.clone(),
+    }
+}
+
+// Arguments for launching `rom` with `tmpl`. Every argument that is exactly `{rom}` becomes the
+// ROM path as-is; `{rom_quoted}` is replaced anywhere inside an argument with the shell-quoted
+// path, for programs that take a command string (e.g. a terminal's `-e "emu {rom_quoted}"`).
+pub fn build_command_args(tmpl: &CmdTemplate, rom: &Path) -> Vec<std::ffi::OsString> {
+    let mut args: Vec<std::ffi::OsString> = Vec::new();
+    for a in &template_args(tmpl) {
+        if a == "{rom}" {
+            args.push(rom.as_os_str().to_owned());
+        } else if a.contains("{rom_quoted}") {
+            let quoted = shell_quote(&rom.to_string_lossy());
+            args.push(std::ffi::OsString::from(a.replace("{rom_quoted}", &quoted)));
+        } else {
+            args.push(std::ffi::OsString::from(a));
+        }
+    }
+    args
+}
+
+// Quote `s` for a POSIX shell: wrapped in single quotes, embedded quotes escaped.
+fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+// The script run by `sh -c` for a `shell = true` template: program and args joined with spaces
+// as written, so they can use pipes, `&&` and `$VARS`, with every `{rom}` and `{rom_quoted}`
+// replaced by the quoted ROM path.
+fn shell_script(tmpl: &CmdTemplate, rom: &Path) -> String {
+    let rom = shell_quote(&rom.to_string_lossy());
+    std::iter::once(tmpl.program.clone())
+        .chain(template_args(tmpl))
+        .map(|part| part.replace("{rom_quoted}", &rom).replace("{rom}", &rom))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// The process to start for `rom`: the program itself, or `sh -c` for shell templates. On Unix
+// it leads a new process group, so killing it also takes down anything it started (RetroArch
+// cores, the commands of a shell template).
+fn build_command(tmpl: &CmdTemplate, rom: &Path) -> Command {
+    let mut cmd = if tmpl.shell.unwrap_or(false) {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(shell_script(tmpl, rom));
+        cmd
+    } else {
+        let mut cmd = Command::new(&tmpl.program);
+        cmd.args(build_command_args(tmpl, rom));
+        cmd
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    cmd
+}
+
+// The full command line for launching `rom`, for display. Arguments containing spaces are
+// single-quoted.
+pub fn command_line(tmpl: &CmdTemplate, rom: &Path) -> String {
+    if tmpl.shell.unwrap_or(false) {
+        return format!("sh -c {}", shell_quote(&shell_script(tmpl, rom)));
+    }
+    let mut parts = vec![tmpl.program.clone()];
+    for a in build_command_args(tmpl, rom) {
+        let a = a.to_string_lossy().into_owned();
+        if a.is_empty() || a.contains(char::is_whitespace) {
+            parts.push(format!("'{}'", a));
+        } else {
+            parts.push(a);
+        }
+    }
+    parts.join(" ")
+}
+
+// Where to report the running ROM for external tools (e.g. a stream overlay), and the system
+// it belongs to.
+pub struct StatusFile {
+    pub path: PathBuf,
+    pub system: String,
+}
+
+impl StatusFile {
+    // Record `rom` as running.
+    fn started(&self, rom: &Path) {
+        let contents = format!("system={}\nrom={}\n", self.system, rom.display());
+        if let Err(e) = write_atomic(&self.path, &contents) {
+            eprintln!("Failed writing status file {}: {}", self.path.display(), e);
+        }
+    }
+
+    // Empty the file once nothing is running.
+    fn cleared(&self) {
+        if let Err(e) = write_atomic(&self.path, "") {
+            eprintln!("Failed writing status file {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, contents.as_bytes())?;
+    std::fs::rename(&tmp, path)
+}
+
+// Point the child's stdout and stderr at a fresh log file at `path`.
+fn redirect_output(cmd: &mut Command, path: &Path) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let out = std::fs::File::create(path)?;
+    let err = out.try_clone()?;
+    cmd.stdout(Stdio::from(out)).stderr(Stdio::from(err));
+    Ok(())
+}
+
+// Last non-empty line of the log at `path`, if any.
+fn last_log_line(path: &Path) -> Option<String> {
+    let contents = std::fs::read_to_string(path).ok()?;
+    contents
+        .lines()
+        .rev()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .map(str::to_string)
+}
+
+// Spawn the emulator for `rom` and wait for it to exit. Returns Err with a user-facing message
+// when the emulator can't be started (with the OS error) or exits with a nonzero code. With
+// `log_path` the emulator's output goes to that file and its last line is added to the error
+// message.
+pub fn spawn_emulator_template(
+    tmpl: &CmdTemplate,
+    rom: &Path,
+    child_slot: Arc<Mutex<Option<std::process::Child>>>,
+    status: Option<&StatusFile>,
+    log_path: Option<&Path>,
+) -> Result<(), String> {
+    let mut cmd = build_command(tmpl, rom);
+    // without the redirect the log on disk is from an earlier run, so it isn't read afterwards
+    let log_path = log_path.filter(|log| {
+        redirect_output(&mut cmd, log)
+            .map_err(|e| eprintln!("Failed to open emulator log {}: {}", log.display(), e))
+            .is_ok()
+    });
+    match cmd.spawn() {
+        Ok(child) => {
+            println!("Launched {} with pid={}", tmpl.program, child.id());
+            if let Some(status) = status {
+                status.started(rom);
+            }
+            // place child into shared slot
+            {
+                let mut slot = child_slot.lock().unwrap();
+                *slot = Some(child);
+            }
+
+            // wait using polling so other threads can lock and kill
+            let mut exit_status: Option<std::process::ExitStatus> = None;
+            loop {
+                // check child status
+                {
+                    let mut slot = child_slot.lock().unwrap();
+                    if let Some(ref mut c) = slot.as_mut() {
+                        match c.try_wait() {
+                            Ok(Some(status)) => {
+                                println!("Emulator exited with {:?}", status);
+                                exit_status = Some(status);
+                                // remove from slot
+                                slot.take();
+                                break;
+                            }
+                            Ok(None) => {
+                                // still running
+                            }
+                            Err(e) => {
+                                eprintln!("Child try_wait error: {}", e);
+                                slot.take();
+                                break;
+                            }
+                        }
+                    } else {
+                        // no child present
+                        break;
+                    }
+                }
+                std::thread::sleep(std::time::Duration::from_millis(150));
+            }
+            println!("Emulator exited");
+            if let Some(status) = status {
+                status.cleared();
+            }
+            // a missing code means the emulator was terminated by a signal (e.g. our kill hotkey)
+            match exit_status.and_then(|s| s.code()) {
+                Some(code) if code != 0 => match log_path.and_then(last_log_line) {
+                    Some(line) => Err(format!(
+                        "{} exited with code {}: {}",
+                        tmpl.program, code, line
+                    )),
+                    None => Err(format!("{} exited with code {}", tmpl.program, code)),
+                },
+                _ => Ok(()),
+            }
+        }
+        Err(e) => {
+            eprintln!("Failed to spawn emulator {}: {}", tmpl.program, e);
+            let program = if tmpl.shell.unwrap_or(false) {
+                "sh"
+            } else {
+                &tmpl.program
+            };
+            Err(format!("Could not start {}: {}", program, e))
+        }
+    }
+}
+
+// True while the emulator in `child_slot` has not exited yet.
+pub fn emulator_running(child_slot: &Mutex<Option<std::process::Child>>) -> bool {
+    let mut slot = child_slot.lock().unwrap();
+    matches!(slot.as_mut().map(|c| c.try_wait()), Some(Ok(None)))
+}
+
+// Kill the emulator's whole process group (its id, see build_command), falling back to the
+// emulator alone if that fails.
+#[cfg(unix)]
+fn kill_child(c: &mut std::process::Child) -> std::io::Result<()> {
+    let pgid = c.id() as libc::pid_t;
+    // SAFETY: kill(2) takes no pointers; a negative pid addresses the process group
+    if unsafe { libc::kill(-pgid, libc::SIGKILL) } == 0 {
+        Ok(())
+    } else {
+        c.kill()
+    }
+}
+
+#[cfg(not(unix))]
+fn kill_child(c: &mut std::process::Child) -> std::io::Result<()> {
+    c.kill()
+}
+
+// Kill the emulator in `child_slot`, if any. Returns a short status message for the UI.
+pub fn kill_current_emulator(child_slot: &Mutex<Option<std::process::Child>>) -> String {
+    let mut slot = child_slot.lock().unwrap();
+    match slot.as_mut() {
+        Some(c) => match kill_child(c) {
+            Ok(_) => "Killed emulator".to_string(),
+            Err(e) => format!("Kill failed: {}", e),
+        },
+        None => "No emulator running".to_string(),
+    }
+}
+
+// Open `dir` with the file manager `program` (e.g. xdg-open) without waiting for it; a background
+// thread reaps the process when it exits. An empty `program` means none is configured.
+pub fn open_folder(program: &str, dir: &Path) -> Result<(), String> {
+    if program.is_empty() {
+        return Err("No file manager configured".to_string());
+    }
+    if which(program).is_none() {
+        return Err(not_found_message(program));
+    }
+    let mut child = Command::new(program)
+        .arg(dir)
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", program, e))?;
+    thread::spawn(move || {
+        let _ = child.wait();
+    });
+    Ok(())
+}
+
+// Run `spawn_emulator_template` on a background thread and report its result on `done`.
+pub fn spawn_in_background(
+    tmpl: CmdTemplate,
+    rom: PathBuf,
+    child_slot: Arc<Mutex<Option<std::process::Child>>>,
+    status: Option<StatusFile>,
+    log_path: Option<PathBuf>,
+    done: mpsc::Sender<Result<(), String>>,
+) {
+    thread::spawn(move || {
+        let _ = done.send(spawn_emulator_template(
+            &tmpl,
+            &rom,
+            child_slot,
+            status.as_ref(),
+            log_path.as_deref(),
+        ));
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template(toml_src: &str) -> CmdTemplate {
+        toml::from_str(toml_src).unwrap()
+    }
+
+    #[test]
+    fn every_rom_token_expands() {
+        let t = template(
+            r#"program = "emu"
+args = ["{rom}", "--save-dir", "/saves", "{rom}"]"#,
+        );
+        let rom = Path::new("/roms/gba/Pokemon Emerald.gba");
+        assert_eq!(
+            build_command_args(&t, rom),
+            vec![
+                "/roms/gba/Pokemon Emerald.gba",
+                "--save-dir",
+                "/saves",
+                "/roms/gba/Pokemon Emerald.gba"
+            ]
+        );
+    }
+
+    #[test]
+    fn rom_paths_with_several_dots_are_passed_whole() {
+        let t = template(
+            r#"program = "snes9x"
+args = ["{rom}"]"#,
+        );
+        for rom in [
+            "/roms/snes/Super Mario World (v1.1).sfc",
+            "/roms/snes/Game.v2.final.sfc",
+            "/roms/snes/.hidden",
+        ] {
+            assert_eq!(build_command_args(&t, Path::new(rom)), vec![rom]);
+        }
+    }
+
+    #[test]
+    fn templates_without_rom_keep_their_args() {
+        let t = template(
+            r#"program = "kodi"
+args = ["--standalone"]"#,
+        );
+        assert_eq!(
+            build_command_args(&t, Path::new("/roms/x.iso")),
+            vec!["--standalone"]
+        );
+        let t = template(r#"program = "kodi""#);
+        assert!(build_command_args(&t, Path::new("/roms/x.iso")).is_empty());
+    }
+
+    #[test]
+    fn rom_is_only_replaced_as_a_whole_argument() {
+        let t = template(
+            r#"program = "emu"
+args = ["--rom={rom}", "{ROM}", "{rom} "]"#,
+        );
+        assert_eq!(
+            build_command_args(&t, Path::new("/roms/a.gb")),
+            vec!["--rom={rom}", "{ROM}", "{rom} "]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_rom_paths_are_passed_unchanged() {
+        use std::os::unix::ffi::OsStrExt;
+        let t = template(
+            r#"program = "emu"
+args = ["{rom}"]"#,
+        );
+        let rom = Path::new(std::ffi::OsStr::from_bytes(b"/roms/caf\xe9.gb"));
+        assert_eq!(build_command_args(&t, rom), vec![rom.as_os_str()]);
+    }
+
+    #[test]
+    fn retroarch_templates_load_the_core_first() {
+        let t = template(
+            r#"program = "retroarch"
+launcher = "retroarch"
+core = "/cores/snes9x_libretro.so""#,
+        );
+        assert_eq!(
+            build_command_args(&t, Path::new("/roms/a b.sfc")),
+            vec!["-L", "/cores/snes9x_libretro.so", "/roms/a b.sfc"]
+        );
+    }
+
+    #[test]
+    fn rom_quoted_is_shell_quoted_inside_an_argument() {
+        let t = template(
+            r#"program = "xterm"
+args = ["-e", "emu --fullscreen {rom_quoted}"]"#,
+        );
+        let rom = Path::new("/roms/Tony's Game.bin");
+        assert_eq!(
+            build_command_args(&t, rom),
+            vec!["-e", "emu --fullscreen '/roms/Tony'\\''s Game.bin'"]
+        );
+    }
+
+    #[test]
+    fn shell_templates_quote_both_placeholders() {
+        let t = template(
+            r#"program = "emu"
+args = ["{rom}", "&&", "echo", "{rom_quoted}"]
+shell = true"#,
+        );
+        let rom = Path::new("/roms/a b.gb");
+        assert_eq!(
+            shell_script(&t, rom),
+            "emu '/roms/a b.gb' && echo '/roms/a b.gb'"
+        );
+    }
+}
@@ -1,6 +1,8 @@
 use sdl2::controller::Button as CButton;
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
+use sdl2::event::{Event, WindowEvent};
+use sdl2::joystick::HatState;
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::mouse::MouseButton;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 use sdl2::render::Texture;
@@ -8,90 +10,547 @@ use sdl2::ttf::Sdl2TtfContext;
 use sdl2::video::FullscreenType;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::collections::HashSet;
 use std::env;
 #[cfg(feature = "x11")]
 use std::ffi::CString;
 use std::path::{Path, PathBuf};
-use std::process::Command;
 #[cfg(feature = "x11")]
 use std::ptr;
 use std::sync::mpsc;
 use std::sync::{Arc, Mutex};
+#[cfg(feature = "x11")]
 use std::thread;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 #[cfg(feature = "x11")]
 use x11::xlib;
 
+mod cli;
+#[cfg(unix)]
+mod control;
+mod dupes;
+mod emu;
+mod marquee;
+mod meta;
+mod paths;
+mod recent;
+mod scrape;
+mod style;
+mod text;
+mod volume;
+
 const TILE_H: i32 = 140;
+const TILE_PADDING: i32 = 10;
+// smallest tile height accepted from the style; keeps the visible-row math away from zero
+const MIN_TILE_H: i32 = 24;
+// UI font size in points, and the larger size used when `accessibility` is enabled
+const FONT_SIZE: u16 = 14;
+const ACCESSIBLE_FONT_SIZE: u16 = 24;
+// `which` of mouse events SDL synthesizes from touches (SDL_TOUCH_MOUSEID); touches are handled
+// through the finger events instead so these are skipped
+const TOUCH_MOUSE_ID: u32 = u32::MAX;
+// system tab bar (below the banner): bar height and horizontal padding inside each tab
+const TAB_BAR_H: i32 = 32;
+const TAB_PAD: i32 = 12;
+// seconds the boot-time autolaunch countdown waits for a button press before launching
+const AUTOLAUNCH_SECS: u64 = 5;
+// seconds each ROM name stays up in the slideshow attract mode
+const ATTRACT_SLIDE_SECS: u64 = 6;
+// volume change per key press, in percent, and how long the volume bar stays up
+const VOLUME_STEP: i8 = 5;
+const VOLUME_OSD_SECS: u64 = 2;
+// an emulator failing within this many seconds of starting counts toward launch_fail_limit
+const QUICK_FAIL_SECS: u64 = 10;
+
+// A directory the scan could not read (permissions, unmounted drive, ...).
+#[derive(Debug)]
+struct ScanError {
+    path: PathBuf,
+    error: String,
+}
+
+impl std::fmt::Display for ScanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Could not read {}: {}", self.path.display(), self.error)
+    }
+}
+
+// One-line summary of scan errors for the UI, or None if there were none.
+fn scan_error_message(errors: &[ScanError]) -> Option<String> {
+    match errors {
+        [] => None,
+        [e] => Some(e.to_string()),
+        [e, rest @ ..] => Some(format!("{} (and {} more)", e, rest.len())),
+    }
+}
+
+// File type of a directory entry, resolving symlinks to their target when `follow_symlinks` is
+// set. None for unreadable entries, dangling links and (when not following) any symlink.
+fn entry_file_type(e: &std::fs::DirEntry, follow_symlinks: bool) -> Option<std::fs::FileType> {
+    entry_type_and_len(e, follow_symlinks, false).map(|(ft, _)| ft)
+}
+
+// As entry_file_type, plus the size of files when `with_len` is set (0 otherwise). A followed
+// symlink's size comes from the stat that resolves its type, so only plain files need another.
+fn entry_type_and_len(
+    e: &std::fs::DirEntry,
+    follow_symlinks: bool,
+    with_len: bool,
+) -> Option<(std::fs::FileType, u64)> {
+    let ft = e.file_type().ok()?;
+    if ft.is_symlink() {
+        if !follow_symlinks {
+            return None;
+        }
+        let m = std::fs::metadata(e.path()).ok()?;
+        return Some((m.file_type(), m.len()));
+    }
+    let len = if with_len && ft.is_file() {
+        e.metadata().ok()?.len()
+    } else {
+        0
+    };
+    Some((ft, len))
+}
 
-fn scan_grouped(root: &Path, cfg: &ConfigFile) -> HashMap<String, Vec<PathBuf>> {
+// Scan `root` into ROM lists per system. Also returns the ROMs that have a `.meta.toml` next to
+// them and the folders that could not be read.
+fn scan_grouped(
+    root: &Path,
+    cfg: &ConfigFile,
+) -> (
+    HashMap<String, Vec<PathBuf>>,
+    HashSet<PathBuf>,
+    Vec<ScanError>,
+) {
     // group files by the top-level folder under root: roms/<system>/...
     let mut groups: HashMap<String, Vec<PathBuf>> = HashMap::new();
-    let ignored_exts = ["zip", "7z", "rar", "gz", "xz"];
+    let mut errors: Vec<ScanError> = Vec::new();
+    // metadata files seen; matched up with their ROMs once the scan is done
+    let mut meta_files: HashSet<PathBuf> = HashSet::new();
+
+    let follow_symlinks = cfg.follow_symlinks.unwrap_or(true);
+    // canonical paths of directories already scanned, so symlink cycles can't loop forever
+    let mut visited: HashSet<PathBuf> = HashSet::new();
+
+    // folders below a system folder are only entered up to max_scan_depth levels deep
+    let max_depth = cfg.max_scan_depth.unwrap_or(usize::MAX);
+
+    // group_by = "extension": files anywhere under root go to the first system (in display
+    // order) that lists their extension, and root itself plays the part of a system folder
+    let by_extension = cfg.group_by.as_deref() == Some("extension");
+    let root_is_system = usize::from(by_extension);
+    let ext_order = configured_systems_ordered(cfg);
+    // files smaller than this are placeholders, not ROMs
+    let min_size = cfg.min_rom_size.unwrap_or(0);
+    let ignore_hidden = cfg.ignore_hidden.unwrap_or(true);
+
+    // each entry carries its depth under root: 0 for root itself, 1 for a system folder
+    let mut stack: Vec<(PathBuf, usize)> = vec![(root.to_path_buf(), 0)];
+    while let Some((cur, depth)) = stack.pop() {
+        if let Ok(canonical) = cur.canonicalize() {
+            if !visited.insert(canonical) {
+                continue;
+            }
+        }
+        let entries = match cur.read_dir() {
+            Ok(entries) => entries,
+            Err(e) => {
+                eprintln!("Could not read {}: {}", cur.display(), e);
+                errors.push(ScanError {
+                    path: cur,
+                    error: e.kind().to_string(),
+                });
+                continue;
+            }
+        };
+        for e in entries.flatten() {
+            if ignore_hidden && is_hidden_or_junk(&e.file_name()) {
+                continue;
+            }
+            let p = e.path();
+            match entry_type_and_len(&e, follow_symlinks, min_size > 0) {
+                // a subfolder of `cur` sits `depth` levels below its system folder
+                Some((ft, _)) if ft.is_dir() && depth + root_is_system <= max_depth => {
+                    stack.push((p, depth + 1))
+                }
+                Some((ft, _)) if ft.is_file() && meta::is_meta_file(&p) => {
+                    meta_files.insert(p);
+                }
+                Some((ft, len)) if ft.is_file() && len >= min_size => {
+                    if let Some(sys_l) = rom_system(&p, root, cfg, &ext_order) {
+                        groups.entry(sys_l).or_default().push(p);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // an .m3u playlist is the launchable entry for a multi-disc game; hide the discs it lists
+    for v in groups.values_mut() {
+        hide_m3u_referenced_files(v);
+    }
+
+    // optionally drop save/sidecar files that sit next to a launchable ROM with the same stem
+    if cfg.hide_sidecars.unwrap_or(false) {
+        for (sys, v) in groups.iter_mut() {
+            if let Some(tmpl) = cfg.systems.as_ref().and_then(|m| m.get(sys)) {
+                if let Some(launchable) = tmpl.extensions.as_ref().or(visible_extensions(cfg, tmpl))
+                {
+                    hide_sidecar_files(v, launchable);
+                }
+            }
+        }
+    }
+
+    // sort file lists for each system; natural_sort puts "Game 2" before "Game 10".
+    // show_subfolders sorts folder by folder, loose files first, so each subfolder stays together
+    let natural = cfg.natural_sort.unwrap_or(true);
+    let by_folder = cfg.show_subfolders.unwrap_or(false);
+    let cmp = |a: &Path, b: &Path| {
+        if natural {
+            text::natural_cmp(&a.to_string_lossy(), &b.to_string_lossy())
+        } else {
+            a.cmp(b)
+        }
+    };
+    for v in groups.values_mut() {
+        if by_folder {
+            v.sort_by(|a, b| {
+                let parent = |p: &PathBuf| p.parent().map(Path::to_path_buf).unwrap_or_default();
+                cmp(&parent(a), &parent(b)).then_with(|| cmp(a, b))
+            });
+        } else {
+            v.sort_by(|a, b| cmp(a, b));
+        }
+    }
+
+    let with_meta: HashSet<PathBuf> = groups
+        .values()
+        .flatten()
+        .filter(|rom| meta_files.contains(&meta::meta_path(rom)))
+        .cloned()
+        .collect();
+    (groups, with_meta, errors)
+}
+
+// The system the file `p` under `root` is listed in, or None if it isn't listed: its system is
+// not configured, or its extension is ignored or not visible there. Only looks at the path, so
+// the scan's filesystem checks (hidden files, size, depth) come first. `ext_order` is
+// configured_systems_ordered, for group_by = "extension".
+fn rom_system(p: &Path, root: &Path, cfg: &ConfigFile, ext_order: &[String]) -> Option<String> {
+    let ext = p.extension().and_then(|s| s.to_str());
+    let sys_l = if cfg.group_by.as_deref() == Some("extension") {
+        system_claiming_extension(ext?, cfg, ext_order)?
+    } else {
+        // the top-level folder under root: roms/<system>/...
+        p.strip_prefix(root)
+            .ok()?
+            .iter()
+            .next()?
+            .to_str()?
+            .to_lowercase()
+    };
+    // only include if systems are configured and contain this key
+    let tmpl = cfg.systems.as_ref()?.get(&sys_l)?;
+    // skip ignored extensions (archives by default)
+    if ext.is_some_and(|ext| is_ignored_extension(ext, cfg, tmpl)) {
+        return None;
+    }
+    // if visible_extensions is set, only include matching extensions
+    if let Some(visible) = visible_extensions(cfg, tmpl) {
+        let shown = ext.is_some_and(|ext| {
+            visible
+                .iter()
+                .any(|e| e.to_lowercase() == ext.to_lowercase())
+        });
+        if !shown {
+            return None;
+        }
+    }
+    Some(sys_l)
+}
 
-    let mut stack: Vec<PathBuf> = vec![root.to_path_buf()];
-    while let Some(cur) = stack.pop() {
-        if let Ok(entries) = cur.read_dir() {
-            for e in entries.flatten() {
+// A folder directly under the roms root that has files but no `systems` entry.
+struct UnconfiguredFolder {
+    name: String,
+    files: usize,
+    // most common extensions first, at most 5
+    extensions: Vec<String>,
+}
+
+// Top-level folders under `root` that contain files but are not configured as systems, with a
+// sample of their extensions as a hint for which emulator to configure.
+fn unconfigured_folders(root: &Path, cfg: &ConfigFile) -> Vec<UnconfiguredFolder> {
+    // folder names don't matter when grouping by extension
+    if cfg.group_by.as_deref() == Some("extension") {
+        return Vec::new();
+    }
+    let follow_symlinks = cfg.follow_symlinks.unwrap_or(true);
+    let entries = match root.read_dir() {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let ignore_hidden = cfg.ignore_hidden.unwrap_or(true);
+    let mut folders: Vec<UnconfiguredFolder> = Vec::new();
+    for e in entries.flatten() {
+        if ignore_hidden && is_hidden_or_junk(&e.file_name()) {
+            continue;
+        }
+        if !entry_file_type(&e, follow_symlinks).is_some_and(|ft| ft.is_dir()) {
+            continue;
+        }
+        let name = e.file_name().to_string_lossy().into_owned();
+        if cfg
+            .systems
+            .as_ref()
+            .is_some_and(|m| m.contains_key(&name.to_lowercase()))
+        {
+            continue;
+        }
+        let mut files = 0;
+        let mut ext_counts: HashMap<String, usize> = HashMap::new();
+        let mut visited: HashSet<PathBuf> = HashSet::new();
+        let mut stack = vec![e.path()];
+        while let Some(cur) = stack.pop() {
+            if let Ok(canonical) = cur.canonicalize() {
+                if !visited.insert(canonical) {
+                    continue;
+                }
+            }
+            for e in cur.read_dir().into_iter().flatten().flatten() {
+                if ignore_hidden && is_hidden_or_junk(&e.file_name()) {
+                    continue;
+                }
                 let p = e.path();
-                match e.file_type() {
-                    Ok(ft) if ft.is_dir() => stack.push(p),
-                    Ok(ft) if ft.is_file() => {
-                        // ignore archive files
+                match entry_file_type(&e, follow_symlinks) {
+                    Some(ft) if ft.is_dir() => stack.push(p),
+                    Some(ft) if ft.is_file() && !meta::is_meta_file(&p) => {
+                        files += 1;
                         if let Some(ext) = p.extension().and_then(|s| s.to_str()) {
-                            if ignored_exts.contains(&ext.to_lowercase().as_str()) {
-                                continue;
-                            }
-                        }
-                        if let Ok(rel) = p.strip_prefix(root) {
-                            let mut iter = rel.iter();
-                            if let Some(first) = iter.next() {
-                                if let Some(sys) = first.to_str() {
-                                    let sys_l = sys.to_lowercase();
-                                    // only include if systems are configured and contain this key
-                                    if let Some(systems) = cfg.systems.as_ref() {
-                                        if let Some(tmpl) = systems.get(&sys_l) {
-                                            // if visible_extensions is set, only include matching extensions
-                                            if let Some(visible) = tmpl.visible_extensions.as_ref()
-                                            {
-                                                if let Some(ext) =
-                                                    p.extension().and_then(|s| s.to_str())
-                                                {
-                                                    if visible.iter().any(|e| {
-                                                        e.to_lowercase() == ext.to_lowercase()
-                                                    }) {
-                                                        groups
-                                                            .entry(sys_l)
-                                                            .or_default()
-                                                            .push(p.clone());
-                                                    }
-                                                }
-                                            } else {
-                                                groups.entry(sys_l).or_default().push(p.clone());
-                                            }
-                                        }
-                                    }
-                                }
-                            }
+                            *ext_counts.entry(normalize_ext(ext)).or_default() += 1;
                         }
                     }
                     _ => {}
                 }
             }
         }
+        if files == 0 {
+            continue;
+        }
+        let mut exts: Vec<(String, usize)> = ext_counts.into_iter().collect();
+        exts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        folders.push(UnconfiguredFolder {
+            name,
+            files,
+            extensions: exts.into_iter().take(5).map(|(ext, _)| ext).collect(),
+        });
     }
+    folders.sort_by(|a, b| a.name.cmp(&b.name));
+    folders
+}
 
-    // sort file lists for each system
-    for v in groups.values_mut() {
-        v.sort();
+// File size for the detail view, e.g. "512 B", "1.4 MB".
+fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+fn is_m3u(p: &Path) -> bool {
+    p.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("m3u"))
+        .unwrap_or(false)
+}
+
+// Remove the files referenced by any .m3u playlist in `files`. Playlist entries are paths
+// relative to the playlist's folder (absolute paths also work); blank and `#` lines are skipped.
+fn hide_m3u_referenced_files(files: &mut Vec<PathBuf>) {
+    let mut referenced: HashSet<PathBuf> = HashSet::new();
+    for f in files.iter().filter(|f| is_m3u(f)) {
+        let dir = f.parent().unwrap_or(Path::new(""));
+        match std::fs::read_to_string(f) {
+            Ok(contents) => {
+                for line in contents.lines() {
+                    let line = line.trim_start_matches('\u{feff}').trim();
+                    if line.is_empty() || line.starts_with('#') {
+                        continue;
+                    }
+                    // collecting components drops `./` so entries compare equal to scanned paths
+                    referenced.insert(dir.join(line).components().collect());
+                }
+            }
+            Err(e) => eprintln!("Failed to read playlist {}: {}", f.display(), e),
+        }
+    }
+    if !referenced.is_empty() {
+        files.retain(|f| is_m3u(f) || !referenced.contains(f));
+    }
+}
+
+// Split a file stem around a "(Disc N)" / "(Disk N)" marker, returning the stem without the
+// marker and the disc number. "(Disc 1 of 2)" is also recognised.
+fn split_disc_marker(stem: &str) -> Option<(String, u32)> {
+    // ASCII lowercasing keeps byte offsets valid for slicing the original stem
+    let lower = stem.to_ascii_lowercase();
+    for tag in ["(disc ", "(disk "] {
+        if let Some(start) = lower.find(tag) {
+            let rest = &lower[start + tag.len()..];
+            let end = rest.find(')')?;
+            let n: u32 = rest[..end].split_whitespace().next()?.parse().ok()?;
+            let base = format!("{}{}", &stem[..start], &stem[start + tag.len() + end + 1..]);
+            return Some((base.trim().to_string(), n));
+        }
+    }
+    None
+}
+
+// Collapse files in each group that differ only by their "(Disc N)" marker into one entry (the
+// lowest disc). Returns a map from that entry to all of its discs, in disc order.
+fn merge_disc_sets(groups: &mut HashMap<String, Vec<PathBuf>>) -> HashMap<PathBuf, Vec<PathBuf>> {
+    let mut disc_sets = HashMap::new();
+    for files in groups.values_mut() {
+        // (folder, stem without marker, extension) -> discs
+        let mut sets: HashMap<(PathBuf, String, String), Vec<(u32, PathBuf)>> = HashMap::new();
+        for f in files.iter() {
+            if let Some((base, n)) = f
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(split_disc_marker)
+            {
+                let parent = f.parent().map(Path::to_path_buf).unwrap_or_default();
+                let ext = f
+                    .extension()
+                    .and_then(|e| e.to_str())
+                    .unwrap_or("")
+                    .to_lowercase();
+                sets.entry((parent, base.to_lowercase(), ext))
+                    .or_default()
+                    .push((n, f.clone()));
+            }
+        }
+        for (_, mut discs) in sets {
+            if discs.len() < 2 {
+                continue;
+            }
+            discs.sort();
+            let discs: Vec<PathBuf> = discs.into_iter().map(|(_, p)| p).collect();
+            {
+                let hidden: HashSet<&PathBuf> = discs[1..].iter().collect();
+                files.retain(|f| !hidden.contains(f));
+            }
+            disc_sets.insert(discs[0].clone(), discs);
+        }
+    }
+    disc_sets
+}
+
+// Remove files whose stem matches a launchable file in the same folder but whose own extension
+// is not launchable, e.g. `Game.sav` next to `Game.gba`, or `Game (Disc 1).bin` next to
+// `Game (Disc 1).cue` when only `cue` is listed.
+fn hide_sidecar_files(files: &mut Vec<PathBuf>, launchable: &[String]) {
+    let is_launchable = |p: &PathBuf| {
+        p.extension()
+            .and_then(|e| e.to_str())
+            .map(|e| {
+                launchable
+                    .iter()
+                    .any(|l| normalize_ext(l) == e.to_lowercase())
+            })
+            .unwrap_or(false)
+    };
+    // folder + stem of every launchable file
+    let primary: HashSet<PathBuf> = files
+        .iter()
+        .filter(|p| is_launchable(p))
+        .map(|p| p.with_extension(""))
+        .collect();
+    files.retain(|p| is_launchable(p) || !primary.contains(&p.with_extension("")));
+}
+
+// extensions skipped by the scan when neither the system nor the config lists its own
+const DEFAULT_IGNORED_EXTS: [&str; 5] = ["zip", "7z", "rar", "gz", "xz"];
+
+// files that operating systems and file managers leave in folders, compared lowercased
+const JUNK_FILES: [&str; 3] = ["thumbs.db", "ehthumbs.db", "desktop.ini"];
+
+// Whether an entry is skipped by ignore_hidden: dotfiles and dot-folders (`.DS_Store`,
+// `._Game.gba`, `.Trashes`) and the JUNK_FILES.
+fn is_hidden_or_junk(name: &std::ffi::OsStr) -> bool {
+    let name = name.to_string_lossy().to_lowercase();
+    name.starts_with('.') || JUNK_FILES.contains(&name.as_str())
+}
+
+// Normalize an extension from config for comparison: trimmed, no leading dot, lowercase.
+fn normalize_ext(e: &str) -> String {
+    e.trim().trim_start_matches('.').to_lowercase()
+}
+
+// Whether files with extension `ext` are skipped for a system. The system's own
+// `ignored_extensions` overrides the global list, which overrides the built-in archive list.
+fn is_ignored_extension(ext: &str, cfg: &ConfigFile, tmpl: &CmdTemplate) -> bool {
+    let ext_l = normalize_ext(ext);
+    match tmpl
+        .ignored_extensions
+        .as_ref()
+        .or(cfg.ignored_extensions.as_ref())
+    {
+        Some(list) => list.iter().any(|e| normalize_ext(e) == ext_l),
+        None => DEFAULT_IGNORED_EXTS.contains(&ext_l.as_str()),
     }
-    groups
+}
+
+// The extensions shown for a system: its own `visible_extensions`, else the global list. None
+// shows every extension (that isn't ignored).
+fn visible_extensions<'a>(cfg: &'a ConfigFile, tmpl: &'a CmdTemplate) -> Option<&'a Vec<String>> {
+    tmpl.visible_extensions
+        .as_ref()
+        .or(cfg.visible_extensions.as_ref())
+}
+
+// Every configured system, hidden ones included, in display order.
+fn configured_systems_ordered(cfg: &ConfigFile) -> Vec<String> {
+    let mut order: Vec<String> = cfg
+        .systems
+        .as_ref()
+        .map(|m| m.keys().cloned().collect())
+        .unwrap_or_default();
+    order_systems(&mut order, cfg.system_order.as_deref().unwrap_or(&[]));
+    order
+}
+
+// The system a file with extension `ext` is grouped under with group_by = "extension": the
+// first in `order` whose `extensions` (or, if unset, `visible_extensions`) list it. Hidden
+// systems are skipped, since their ROMs could not be browsed.
+fn system_claiming_extension(ext: &str, cfg: &ConfigFile, order: &[String]) -> Option<String> {
+    let ext_l = normalize_ext(ext);
+    let systems = cfg.systems.as_ref()?;
+    order
+        .iter()
+        .find(|sys| {
+            systems
+                .get(*sys)
+                .filter(|t| !t.hidden.unwrap_or(false))
+                .and_then(|t| t.extensions.as_ref().or(t.visible_extensions.as_ref()))
+                .is_some_and(|exts| exts.iter().any(|e| normalize_ext(e) == ext_l))
+        })
+        .cloned()
 }
 
 fn find_system_for_extension(
     ext: &str,
     cfg: &ConfigFile,
-    systems_order: &Vec<String>,
+    systems_order: &[String],
 ) -> Option<String> {
     let ext_l = ext.to_lowercase();
     if let Some(systems) = cfg.systems.as_ref() {
@@ -110,22 +569,147 @@ fn find_system_for_extension(
     None
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
-struct CmdTemplate {
-    program: String,
-    args: Vec<String>,
+// Pick the template used to launch `rom` from `system`: the system's own mapping, else the first
+// configured system (in display order, hidden ones included) whose `extensions` list the ROM's
+// extension. The program must exist.
+fn resolve_launch_template(
+    cfg: &ConfigFile,
+    system: &str,
+    rom: &Path,
+) -> Result<CmdTemplate, String> {
+    let systems = cfg.systems.as_ref();
+    let tmpl = systems.and_then(|m| m.get(system)).or_else(|| {
+        let ext = rom.extension().and_then(|s| s.to_str())?;
+        let found = find_system_for_extension(ext, cfg, &configured_systems_ordered(cfg))?;
+        systems.and_then(|m| m.get(&found))
+    });
+    match tmpl {
+        Some(t) if emu::program_missing(t) => Err(emu::not_found_message(&t.program)),
+        Some(t) => Ok(t.clone()),
+        None => Err(format!("No emulator configured for system {}", system)),
+    }
+}
+
+// Label for an emulator in the picker: its display_name, else the command line.
+fn emulator_label(t: &CmdTemplate) -> String {
+    t.display_name
+        .clone()
+        .unwrap_or_else(|| format!("{} {}", t.program, t.args.join(" ")))
+}
+
+// Systems whose configured emulator program cannot be found; these are dimmed in the list.
+fn missing_emulator_systems(cfg: &ConfigFile) -> HashSet<String> {
+    let mut missing = HashSet::new();
+    if let Some(systems) = cfg.systems.as_ref() {
+        for (k, tmpl) in systems.iter() {
+            if emu::program_missing(tmpl) {
+                eprintln!(
+                    "Emulator for system {}: {}",
+                    k,
+                    emu::not_found_message(&tmpl.program)
+                );
+                missing.insert(k.to_lowercase());
+            }
+        }
+    }
+    missing
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CmdTemplate {
+    pub program: String,
+    // may be left out for `launcher = "retroarch"`, which defaults it to `{rom}`
+    #[serde(default)]
+    pub args: Vec<String>,
     extensions: Option<Vec<String>>,
     visible_extensions: Option<Vec<String>>,
+    ignored_extensions: Option<Vec<String>>,
+    display_name: Option<String>,
+    // other emulators that can run this system's ROMs, offered by the emulator picker
+    alt_programs: Option<Vec<CmdTemplate>>,
+    // prompt shown before launching when confirm_launch is on
+    confirm_message: Option<String>,
+    // launcher type that shapes the arguments; "retroarch" puts `-L <core>` before them
+    launcher: Option<String>,
+    // libretro core for the retroarch launcher
+    core: Option<String>,
+    // run through `sh -c` so args can use shell syntax; only `{rom}`/`{rom_quoted}` are quoted
+    shell: Option<bool>,
+    // reminder shown while the emulator starts, e.g. its menu hotkey
+    post_launch_notice: Option<String>,
+    // not shown as a system of its own; still used for launching by extension
+    hidden: Option<bool>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 struct ConfigFile {
+    // schema version; configs without one are treated as version 0 and migrated on load
+    version: Option<u32>,
     default: Option<CmdTemplate>,
     systems: Option<HashMap<String, CmdTemplate>>,
     show_empty_systems: Option<bool>,
     controller_map: Option<HashMap<String, String>>,
     default_roms_path: Option<String>,
     font_path: Option<String>,
+    ignored_extensions: Option<Vec<String>>,
+    hide_sidecars: Option<bool>,
+    merge_discs: Option<bool>,
+    controller_db_path: Option<String>,
+    rumble: Option<bool>,
+    system_order: Option<Vec<String>>,
+    launch_timeout_secs: Option<u64>,
+    accessibility: Option<bool>,
+    show_system_tabs: Option<bool>,
+    control_socket: Option<String>,
+    status_file: Option<String>,
+    autolaunch_last: Option<bool>,
+    follow_symlinks: Option<bool>,
+    max_scan_depth: Option<usize>,
+    show_played_badge: Option<bool>,
+    confirm_launch: Option<bool>,
+    show_system_overview: Option<bool>,
+    dry_run: Option<bool>,
+    capture_output: Option<bool>,
+    natural_sort: Option<bool>,
+    scraper_program: Option<String>,
+    scraper_args: Option<Vec<String>>,
+    refocus_prompt: Option<bool>,
+    max_content_width: Option<u32>,
+    texture_cache_size: Option<usize>,
+    attract_mode: Option<String>,
+    attract_timeout_secs: Option<u64>,
+    volume_control: Option<bool>,
+    volume_mixer: Option<String>,
+    group_by: Option<String>,
+    duplicate_hash: Option<bool>,
+    file_manager: Option<String>,
+    show_subfolders: Option<bool>,
+    min_rom_size: Option<u64>,
+    back_action: Option<String>,
+    navigation: Option<String>,
+    visible_extensions: Option<Vec<String>>,
+    ignore_hidden: Option<bool>,
+    show_extensions: Option<bool>,
+    confirm_kill: Option<bool>,
+    menu_chord: Option<Vec<String>>,
+    menu_chord_hold_ms: Option<u64>,
+    exit_chord: Option<Vec<String>>,
+    window_mode: Option<String>,
+    window_size: Option<[u32; 2]>,
+    display_index: Option<i32>,
+    marquee_display: Option<i32>,
+    marquee_art_dir: Option<String>,
+    keyboard_map: Option<HashMap<String, String>>,
+    error_overlay_secs: Option<u64>,
+    message_overlay_secs: Option<u64>,
+    key_repeat_delay_ms: Option<u64>,
+    key_repeat_interval_ms: Option<u64>,
+    system_sort: Option<String>,
+    launch_fail_limit: Option<u32>,
+    launch_fail_cooldown_secs: Option<u64>,
+    system_axes: Option<Vec<u8>>,
+    list_axes: Option<Vec<u8>>,
+    hide_on_launch: Option<bool>,
 }
 
 fn user_config_path() -> Option<std::path::PathBuf> {
@@ -163,36 +747,227 @@ fn write_default_config(path: &Path) -> std::io::Result<()> {
     Ok(())
 }
 
-fn load_config() -> ConfigFile {
+// Current config schema version, written to new and migrated configs.
+const CONFIG_VERSION: u32 = 1;
+
+// Upgrade a parsed config in place to CONFIG_VERSION.
+// Returns true if a step changed settings and the file should be rewritten. Bumping `version`
+// alone doesn't count: rewriting drops the user's comments and layout, which isn't worth it.
+fn migrate_config(value: &mut toml::Value) -> bool {
+    let Some(table) = value.as_table_mut() else {
+        return false;
+    };
+    let version = table
+        .get("version")
+        .and_then(|v| v.as_integer())
+        .unwrap_or(0);
+    if version >= CONFIG_VERSION as i64 {
+        return false;
+    }
+    // Steps run in order, each guarded by `if version < N`, renaming or filling keys in `table`
+    // and setting `changed`. v0 -> v1 only introduced the version field itself, so there is no
+    // step for it.
+    let changed = false;
+    table.insert(
+        "version".to_string(),
+        toml::Value::Integer(CONFIG_VERSION as i64),
+    );
+    changed
+}
+
+// A free name for a backup of `path`: config.toml.bak, or config.toml.bak.1, .2, ... when earlier
+// backups are already there, so none is overwritten.
+fn backup_path(path: &Path) -> PathBuf {
+    let bak = path.with_extension("toml.bak");
+    let mut candidate = bak.clone();
+    let mut n = 1;
+    while candidate.exists() {
+        candidate = PathBuf::from(format!("{}.{}", bak.display(), n));
+        n += 1;
+    }
+    candidate
+}
+
+// Write a migrated config back to `path`, keeping the original next to it (see backup_path)
+// since rewriting drops comments.
+fn rewrite_migrated_config(path: &Path, original: &str, value: &toml::Value) -> Result<(), String> {
+    let s =
+        toml::to_string_pretty(value).map_err(|e| format!("Failed to serialize config: {}", e))?;
+    std::fs::write(backup_path(path), original.as_bytes())
+        .map_err(|e| format!("Failed writing config backup: {}", e))?;
+    let tmp = path.with_extension("toml.tmp");
+    std::fs::write(&tmp, s.as_bytes()).map_err(|e| format!("Failed writing tmp config: {}", e))?;
+    std::fs::rename(&tmp, path).map_err(|e| format!("Failed renaming config: {}", e))?;
+    Ok(())
+}
+
+// A loaded config or style, plus what happened to its file: `created` if it did not exist and
+// was just written from the sample (first run), `parse_error` if it could not be read and the
+// defaults are used instead.
+pub struct Loaded<T> {
+    pub value: T,
+    pub created: bool,
+    pub parse_error: Option<String>,
+}
+
+// A parse error on one line for the overlay: toml's messages span several lines and quote the
+// offending source between `|` gutters, which is dropped.
+fn error_summary(e: &str) -> String {
+    e.lines()
+        .map(str::trim)
+        .filter(|l| !l.is_empty() && !l.contains('|'))
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+// --reset-config: rename `path` to a backup_path if it exists and write a fresh default in its
+// place with `write_default`. Returns a line for the user saying what was done.
+fn reset_to_default(
+    path: &Path,
+    write_default: fn(&Path) -> std::io::Result<()>,
+) -> Result<String, String> {
+    let mut msg = String::new();
+    if path.exists() {
+        let bak = backup_path(path);
+        std::fs::rename(path, &bak)
+            .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+        msg = format!("Moved {} to {}\n", path.display(), bak.display());
+    }
+    write_default(path).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    msg.push_str(&format!("Wrote default {}", path.display()));
+    Ok(msg)
+}
+
+// Load the config at `path` (normally user_config_path()), writing the sample there first if
+// the file doesn't exist. Built-in defaults fill anything the file leaves out.
+fn load_config(path: Option<&Path>) -> Loaded<ConfigFile> {
     // default in-memory config if file missing
     let mut cfg = ConfigFile {
+        version: Some(CONFIG_VERSION),
         default: Some(CmdTemplate {
             program: "mgba-qt".to_string(),
             args: vec!["{rom}".to_string()],
             extensions: None,
             visible_extensions: None,
+            ignored_extensions: None,
+            display_name: None,
+            alt_programs: None,
+            confirm_message: None,
+            launcher: None,
+            core: None,
+            shell: None,
+            post_launch_notice: None,
+            hidden: None,
         }),
         systems: None,
         show_empty_systems: Some(false),
         controller_map: None,
         default_roms_path: None,
         font_path: None,
+        ignored_extensions: None,
+        hide_sidecars: Some(false),
+        merge_discs: Some(false),
+        controller_db_path: None,
+        rumble: Some(false),
+        system_order: None,
+        launch_timeout_secs: Some(30),
+        accessibility: Some(false),
+        show_system_tabs: Some(false),
+        control_socket: None,
+        status_file: None,
+        autolaunch_last: Some(false),
+        follow_symlinks: Some(true),
+        max_scan_depth: None,
+        show_played_badge: Some(false),
+        confirm_launch: Some(false),
+        show_system_overview: Some(false),
+        dry_run: Some(false),
+        capture_output: Some(false),
+        natural_sort: Some(true),
+        scraper_program: None,
+        scraper_args: None,
+        refocus_prompt: Some(false),
+        max_content_width: None,
+        texture_cache_size: Some(256),
+        attract_mode: Some("off".to_string()),
+        attract_timeout_secs: Some(300),
+        volume_control: Some(false),
+        volume_mixer: Some("Master".to_string()),
+        group_by: Some("folder".to_string()),
+        duplicate_hash: Some(false),
+        file_manager: Some("xdg-open".to_string()),
+        show_subfolders: Some(false),
+        min_rom_size: Some(0),
+        back_action: Some("none".to_string()),
+        navigation: Some("flat".to_string()),
+        visible_extensions: None,
+        ignore_hidden: Some(true),
+        show_extensions: Some(true),
+        confirm_kill: Some(false),
+        menu_chord: Some(vec!["start".to_string()]),
+        menu_chord_hold_ms: Some(0),
+        exit_chord: None,
+        window_mode: Some("fullscreen".to_string()),
+        window_size: None,
+        display_index: Some(0),
+        marquee_display: None,
+        marquee_art_dir: None,
+        keyboard_map: None,
+        error_overlay_secs: Some(3),
+        message_overlay_secs: Some(3),
+        key_repeat_delay_ms: Some(400),
+        key_repeat_interval_ms: Some(100),
+        system_sort: Some("config".to_string()),
+        launch_fail_limit: Some(3),
+        launch_fail_cooldown_secs: Some(60),
+        system_axes: Some(vec![0]),
+        list_axes: Some(vec![1]),
+        hide_on_launch: Some(false),
     };
-    if let Some(p) = user_config_path() {
+    let mut created = false;
+    let mut parse_error = None;
+    if let Some(p) = path {
         if !p.exists() {
             // write default sample for user to edit
-            if let Err(e) = write_default_config(&p) {
-                eprintln!("Failed to write default config: {}", e);
+            match write_default_config(p) {
+                Ok(()) => created = true,
+                Err(e) => eprintln!("Failed to write default config: {}", e),
             }
         }
-        if let Ok(contents) = std::fs::read_to_string(&p) {
-            if let Ok(parsed) = toml::from_str::<ConfigFile>(&contents) {
+        if let Ok(contents) = std::fs::read_to_string(p) {
+            let parsed = toml::from_str::<toml::Value>(&contents)
+                .map_err(|e| e.to_string())
+                .and_then(|mut value| {
+                    let rewrite = migrate_config(&mut value);
+                    let parsed = value
+                        .clone()
+                        .try_into::<ConfigFile>()
+                        .map_err(|e| e.to_string())?;
+                    // only a config that loads is written back, so a broken one stays as it was
+                    if rewrite {
+                        if let Err(e) = rewrite_migrated_config(p, &contents, &value) {
+                            eprintln!("Failed to rewrite migrated config: {}", e);
+                        }
+                    }
+                    Ok(parsed)
+                });
+            if let Err(e) = &parsed {
+                eprintln!("Failed to parse config at {}: {}", p.display(), e);
+                parse_error = Some(format!(
+                    "Config error, using defaults: {}",
+                    error_summary(e)
+                ));
+            }
+            if let Ok(parsed) = parsed {
                 // merge into cfg
+                if parsed.version.is_some() {
+                    cfg.version = parsed.version;
+                }
                 if parsed.default.is_some() {
                     cfg.default = parsed.default;
                 }
                 if parsed.systems.is_some() {
-                    cfg.systems = parsed.systems;
+                    cfg.systems = parsed.systems.map(normalize_system_keys);
                 }
                 if parsed.show_empty_systems.is_some() {
                     cfg.show_empty_systems = parsed.show_empty_systems;
@@ -206,652 +981,3555 @@ fn load_config() -> ConfigFile {
                 if parsed.font_path.is_some() {
                     cfg.font_path = parsed.font_path;
                 }
-            } else {
-                eprintln!("Failed to parse config at {}", p.display());
-            }
-        }
-    }
-    cfg
-}
-
-#[derive(Serialize, Deserialize, Clone, Debug)]
-struct StyleConfig {
-    background: Option<[u8; 3]>,
-    tile_selected: Option<[u8; 3]>,
-    tile_normal: Option<[u8; 3]>,
-    text_primary: Option<[u8; 3]>,
-    text_secondary: Option<[u8; 3]>,
-    banner_bg: Option<[u8; 3]>,
-    banner_text: Option<[u8; 3]>,
-    emu_text: Option<[u8; 3]>,
-    overlay_bg: Option<[u8; 3]>,
-    overlay_alpha: Option<u8>,
-    menu_bg: Option<[u8; 3]>,
-    menu_box: Option<[u8; 3]>,
-    menu_selected: Option<[u8; 3]>,
-    menu_title: Option<[u8; 3]>,
-    menu_text: Option<[u8; 3]>,
-    error_overlay_alpha: Option<u8>,
-    message_overlay_alpha: Option<u8>,
-}
-
-fn user_style_path() -> Option<std::path::PathBuf> {
-    if let Ok(xdg) = std::env::var("XDG_CONFIG_HOME") {
-        let mut p = PathBuf::from(xdg);
-        p.push("rpi_emulator_frontend");
-        p.push("style.toml");
-        Some(p)
-    } else if let Some(home) = dirs::home_dir() {
-        let mut p = home;
-        p.push(".config/rpi_emulator_frontend/style.toml");
-        Some(p)
-    } else {
-        None
-    }
-}
-
-fn write_default_style(path: &Path) -> std::io::Result<()> {
-    if let Some(parent) = path.parent() {
-        std::fs::create_dir_all(parent)?;
-    }
-    // prefer a project-level style.sample.toml if present
-    let sample = if let Ok(s) = std::fs::read_to_string("style.sample.toml") {
-        s
-    } else {
-        include_str!("../style.sample.toml").to_string()
-    };
-    let tmp = path.with_extension("toml.tmp");
-    std::fs::write(&tmp, sample.as_bytes())?;
-    std::fs::rename(&tmp, path)?;
-    Ok(())
-}
-
-fn load_style() -> StyleConfig {
-    // defaults hard-coded if file missing or parse fails
-    let mut s = StyleConfig {
-        background: Some([12, 12, 12]),
-        tile_selected: Some([200, 180, 50]),
-        tile_normal: Some([60, 60, 60]),
-        text_primary: Some([240, 240, 240]),
-        text_secondary: Some([180, 180, 180]),
-        banner_bg: Some([20, 20, 20]),
-        banner_text: Some([220, 220, 220]),
-        emu_text: Some([180, 180, 180]),
-        overlay_bg: Some([0, 0, 0]),
-        overlay_alpha: Some(200),
-        menu_bg: Some([10, 10, 10]),
-        menu_box: Some([40, 40, 40]),
-        menu_selected: Some([80, 80, 80]),
-        menu_title: Some([230, 230, 230]),
-        menu_text: Some([220, 220, 220]),
-        error_overlay_alpha: Some(200),
-        message_overlay_alpha: Some(160),
-    };
-
-    if let Some(p) = user_style_path() {
-        if !p.exists() {
-            if let Err(e) = write_default_style(&p) {
-                eprintln!("Failed to write default style: {}", e);
-            }
-        }
-        if let Ok(contents) = std::fs::read_to_string(&p) {
-            if let Ok(parsed) = toml::from_str::<StyleConfig>(&contents) {
-                // merge parsed into s
-                if parsed.background.is_some() {
-                    s.background = parsed.background;
+                if parsed.ignored_extensions.is_some() {
+                    cfg.ignored_extensions = parsed.ignored_extensions;
                 }
-                if parsed.tile_selected.is_some() {
-                    s.tile_selected = parsed.tile_selected;
+                if parsed.hide_sidecars.is_some() {
+                    cfg.hide_sidecars = parsed.hide_sidecars;
                 }
-                if parsed.tile_normal.is_some() {
-                    s.tile_normal = parsed.tile_normal;
+                if parsed.merge_discs.is_some() {
+                    cfg.merge_discs = parsed.merge_discs;
                 }
-                if parsed.text_primary.is_some() {
-                    s.text_primary = parsed.text_primary;
+                if parsed.controller_db_path.is_some() {
+                    cfg.controller_db_path = parsed.controller_db_path;
                 }
-                if parsed.text_secondary.is_some() {
-                    s.text_secondary = parsed.text_secondary;
+                if parsed.rumble.is_some() {
+                    cfg.rumble = parsed.rumble;
                 }
-                if parsed.banner_bg.is_some() {
-                    s.banner_bg = parsed.banner_bg;
+                if parsed.system_order.is_some() {
+                    cfg.system_order = parsed.system_order;
                 }
-                if parsed.banner_text.is_some() {
-                    s.banner_text = parsed.banner_text;
+                if parsed.launch_timeout_secs.is_some() {
+                    cfg.launch_timeout_secs = parsed.launch_timeout_secs;
                 }
-                if parsed.emu_text.is_some() {
-                    s.emu_text = parsed.emu_text;
+                if parsed.accessibility.is_some() {
+                    cfg.accessibility = parsed.accessibility;
                 }
-                if parsed.overlay_bg.is_some() {
-                    s.overlay_bg = parsed.overlay_bg;
+                if parsed.show_system_tabs.is_some() {
+                    cfg.show_system_tabs = parsed.show_system_tabs;
                 }
-                if parsed.overlay_alpha.is_some() {
-                    s.overlay_alpha = parsed.overlay_alpha;
+                if parsed.control_socket.is_some() {
+                    cfg.control_socket = parsed.control_socket;
                 }
-                if parsed.menu_bg.is_some() {
-                    s.menu_bg = parsed.menu_bg;
+                if parsed.status_file.is_some() {
+                    cfg.status_file = parsed.status_file;
                 }
-                if parsed.menu_box.is_some() {
-                    s.menu_box = parsed.menu_box;
+                if parsed.autolaunch_last.is_some() {
+                    cfg.autolaunch_last = parsed.autolaunch_last;
                 }
-                if parsed.menu_selected.is_some() {
-                    s.menu_selected = parsed.menu_selected;
+                if parsed.follow_symlinks.is_some() {
+                    cfg.follow_symlinks = parsed.follow_symlinks;
                 }
-                if parsed.menu_title.is_some() {
-                    s.menu_title = parsed.menu_title;
+                if parsed.max_scan_depth.is_some() {
+                    cfg.max_scan_depth = parsed.max_scan_depth;
                 }
-                if parsed.menu_text.is_some() {
-                    s.menu_text = parsed.menu_text;
+                if parsed.show_played_badge.is_some() {
+                    cfg.show_played_badge = parsed.show_played_badge;
                 }
-                if parsed.error_overlay_alpha.is_some() {
-                    s.error_overlay_alpha = parsed.error_overlay_alpha;
+                if parsed.confirm_launch.is_some() {
+                    cfg.confirm_launch = parsed.confirm_launch;
                 }
-                if parsed.message_overlay_alpha.is_some() {
-                    s.message_overlay_alpha = parsed.message_overlay_alpha;
+                if parsed.show_system_overview.is_some() {
+                    cfg.show_system_overview = parsed.show_system_overview;
                 }
-            } else {
-                eprintln!("Failed to parse style at {}", p.display());
-            }
-        }
-    }
-
-    s
-}
-
-fn write_config(cfg: &ConfigFile) -> Result<(), String> {
-    if let Some(p) = user_config_path() {
-        if let Some(parent) = p.parent() {
-            if let Err(e) = std::fs::create_dir_all(parent) {
-                return Err(format!("Failed to create config dir: {}", e));
-            }
-        }
-        match toml::to_string_pretty(cfg) {
-            Ok(s) => {
-                let tmp = p.with_extension("toml.tmp");
-                if let Err(e) = std::fs::write(&tmp, s.as_bytes()) {
-                    return Err(format!("Failed writing tmp config: {}", e));
+                if parsed.dry_run.is_some() {
+                    cfg.dry_run = parsed.dry_run;
                 }
-                if let Err(e) = std::fs::rename(&tmp, &p) {
-                    return Err(format!("Failed renaming config: {}", e));
+                if parsed.capture_output.is_some() {
+                    cfg.capture_output = parsed.capture_output;
                 }
-                return Ok(());
-            }
-            Err(e) => return Err(format!("Failed to serialize config: {}", e)),
-        }
-    }
-    Err("No config path available".into())
-}
-
-// deprecated helper removed
-
-fn spawn_emulator_template(
-    tmpl: &CmdTemplate,
-    rom: &Path,
-    child_slot: Arc<Mutex<Option<std::process::Child>>>,
-) {
-    let mut cmd = Command::new(&tmpl.program);
-    let mut args: Vec<std::ffi::OsString> = Vec::new();
-    for a in &tmpl.args {
-        if a == "{rom}" {
-            args.push(rom.as_os_str().to_owned());
-        } else {
-            args.push(std::ffi::OsString::from(a));
-        }
-    }
-    cmd.args(&args);
-    match cmd.spawn() {
-        Ok(child) => {
-            println!("Launched {} with pid={}", tmpl.program, child.id());
-            // place child into shared slot
-            {
-                let mut slot = child_slot.lock().unwrap();
-                *slot = Some(child);
-            }
-
-            // wait using polling so other threads can lock and kill
-            loop {
-                // check child status
-                {
-                    let mut slot = child_slot.lock().unwrap();
-                    if let Some(ref mut c) = slot.as_mut() {
-                        match c.try_wait() {
-                            Ok(Some(status)) => {
-                                println!("Emulator exited with {:?}", status);
-                                // remove from slot
-                                slot.take();
-                                break;
-                            }
-                            Ok(None) => {
-                                // still running
-                            }
-                            Err(e) => {
-                                eprintln!("Child try_wait error: {}", e);
-                                slot.take();
-                                break;
-                            }
-                        }
-                    } else {
-                        // no child present
-                        break;
-                    }
+                if parsed.natural_sort.is_some() {
+                    cfg.natural_sort = parsed.natural_sort;
+                }
+                if parsed.scraper_program.is_some() {
+                    cfg.scraper_program = parsed.scraper_program;
+                }
+                if parsed.scraper_args.is_some() {
+                    cfg.scraper_args = parsed.scraper_args;
+                }
+                if parsed.refocus_prompt.is_some() {
+                    cfg.refocus_prompt = parsed.refocus_prompt;
+                }
+                if parsed.max_content_width.is_some() {
+                    cfg.max_content_width = parsed.max_content_width;
+                }
+                if parsed.texture_cache_size.is_some() {
+                    cfg.texture_cache_size = parsed.texture_cache_size;
+                }
+                if parsed.attract_mode.is_some() {
+                    cfg.attract_mode = parsed.attract_mode;
+                }
+                if parsed.attract_timeout_secs.is_some() {
+                    cfg.attract_timeout_secs = parsed.attract_timeout_secs;
+                }
+                if parsed.volume_control.is_some() {
+                    cfg.volume_control = parsed.volume_control;
+                }
+                if parsed.volume_mixer.is_some() {
+                    cfg.volume_mixer = parsed.volume_mixer;
+                }
+                if parsed.group_by.is_some() {
+                    cfg.group_by = parsed.group_by;
+                }
+                if parsed.duplicate_hash.is_some() {
+                    cfg.duplicate_hash = parsed.duplicate_hash;
+                }
+                if parsed.file_manager.is_some() {
+                    cfg.file_manager = parsed.file_manager;
+                }
+                if parsed.show_subfolders.is_some() {
+                    cfg.show_subfolders = parsed.show_subfolders;
+                }
+                if parsed.min_rom_size.is_some() {
+                    cfg.min_rom_size = parsed.min_rom_size;
+                }
+                if parsed.back_action.is_some() {
+                    cfg.back_action = parsed.back_action;
+                }
+                if parsed.navigation.is_some() {
+                    cfg.navigation = parsed.navigation;
+                }
+                if parsed.visible_extensions.is_some() {
+                    cfg.visible_extensions = parsed.visible_extensions;
+                }
+                if parsed.ignore_hidden.is_some() {
+                    cfg.ignore_hidden = parsed.ignore_hidden;
+                }
+                if parsed.show_extensions.is_some() {
+                    cfg.show_extensions = parsed.show_extensions;
+                }
+                if parsed.confirm_kill.is_some() {
+                    cfg.confirm_kill = parsed.confirm_kill;
+                }
+                if parsed.menu_chord.is_some() {
+                    cfg.menu_chord = parsed.menu_chord;
+                }
+                if parsed.menu_chord_hold_ms.is_some() {
+                    cfg.menu_chord_hold_ms = parsed.menu_chord_hold_ms;
+                }
+                if parsed.exit_chord.is_some() {
+                    cfg.exit_chord = parsed.exit_chord;
+                }
+                if parsed.window_mode.is_some() {
+                    cfg.window_mode = parsed.window_mode;
+                }
+                if parsed.window_size.is_some() {
+                    cfg.window_size = parsed.window_size;
+                }
+                if parsed.display_index.is_some() {
+                    cfg.display_index = parsed.display_index;
+                }
+                if parsed.marquee_display.is_some() {
+                    cfg.marquee_display = parsed.marquee_display;
+                }
+                if parsed.marquee_art_dir.is_some() {
+                    cfg.marquee_art_dir = parsed.marquee_art_dir;
+                }
+                if parsed.keyboard_map.is_some() {
+                    cfg.keyboard_map = parsed.keyboard_map;
+                }
+                if parsed.error_overlay_secs.is_some() {
+                    cfg.error_overlay_secs = parsed.error_overlay_secs;
+                }
+                if parsed.message_overlay_secs.is_some() {
+                    cfg.message_overlay_secs = parsed.message_overlay_secs;
+                }
+                if parsed.key_repeat_delay_ms.is_some() {
+                    cfg.key_repeat_delay_ms = parsed.key_repeat_delay_ms;
+                }
+                if parsed.key_repeat_interval_ms.is_some() {
+                    cfg.key_repeat_interval_ms = parsed.key_repeat_interval_ms;
+                }
+                if parsed.system_sort.is_some() {
+                    cfg.system_sort = parsed.system_sort;
+                }
+                if parsed.launch_fail_limit.is_some() {
+                    cfg.launch_fail_limit = parsed.launch_fail_limit;
+                }
+                if parsed.launch_fail_cooldown_secs.is_some() {
+                    cfg.launch_fail_cooldown_secs = parsed.launch_fail_cooldown_secs;
+                }
+                if parsed.system_axes.is_some() {
+                    cfg.system_axes = parsed.system_axes;
+                }
+                if parsed.list_axes.is_some() {
+                    cfg.list_axes = parsed.list_axes;
+                }
+                if parsed.hide_on_launch.is_some() {
+                    cfg.hide_on_launch = parsed.hide_on_launch;
                 }
-                std::thread::sleep(std::time::Duration::from_millis(150));
             }
-            println!("Emulator exited");
         }
-        Err(e) => eprintln!("Failed to spawn emulator {}: {}", tmpl.program, e),
+    }
+    Loaded {
+        value: cfg,
+        created,
+        parse_error,
     }
 }
 
-fn main() -> Result<(), String> {
-    let roms_arg = env::args().nth(1);
+// Lowercase the keys of a `[systems]` table: folders are matched lowercased and every lookup
+// uses lowercase names, so `SNES` must become `snes`. When several keys collide the one already
+// in lowercase wins, else the first in sorted order; the others are reported and dropped.
+fn normalize_system_keys(systems: HashMap<String, CmdTemplate>) -> HashMap<String, CmdTemplate> {
+    let mut entries: Vec<(String, CmdTemplate)> = systems.into_iter().collect();
+    entries.sort_by(|(a, _), (b, _)| (*a != a.to_lowercase(), a).cmp(&(*b != b.to_lowercase(), b)));
+    let mut out: HashMap<String, CmdTemplate> = HashMap::new();
+    let mut kept: HashMap<String, String> = HashMap::new();
+    for (key, tmpl) in entries {
+        let lower = key.to_lowercase();
+        if let Some(winner) = kept.get(&lower) {
+            eprintln!(
+                "Config: system \"{}\" has the same name as \"{}\" (case is ignored); ignoring it",
+                key, winner
+            );
+            continue;
+        }
+        kept.insert(lower.clone(), key);
+        out.insert(lower, tmpl);
+    }
+    out
+}
+
+fn write_config(cfg: &ConfigFile, path: Option<&Path>) -> Result<(), String> {
+    if let Some(p) = path {
+        if let Some(parent) = p.parent() {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                return Err(format!("Failed to create config dir: {}", e));
+            }
+        }
+        match toml::to_string_pretty(cfg) {
+            Ok(s) => {
+                let tmp = p.with_extension("toml.tmp");
+                if let Err(e) = std::fs::write(&tmp, s.as_bytes()) {
+                    return Err(format!("Failed writing tmp config: {}", e));
+                }
+                if let Err(e) = std::fs::rename(&tmp, p) {
+                    return Err(format!("Failed renaming config: {}", e));
+                }
+                return Ok(());
+            }
+            Err(e) => return Err(format!("Failed to serialize config: {}", e)),
+        }
+    }
+    Err("No config path available".into())
+}
 
-    // load config (writes default sample if needed)
-    let mut config = load_config();
+// deprecated helper removed
 
-    // determine roms dir: prefer CLI arg, else config.default_roms_path, else ./roms
-    let roms_dir = match roms_arg {
-        Some(d) => d,
-        None => config
-            .default_roms_path
-            .clone()
-            .unwrap_or_else(|| "./roms".to_string()),
+// Status file to report a launch from the current system in, if one is configured.
+fn status_file_for(
+    cfg: &ConfigFile,
+    systems_vec: &[String],
+    current_system_idx: usize,
+) -> Option<emu::StatusFile> {
+    cfg.status_file.as_deref().map(|p| emu::StatusFile {
+        path: PathBuf::from(paths::expand_path(p)),
+        system: systems_vec
+            .get(current_system_idx)
+            .cloned()
+            .unwrap_or_default(),
+    })
+}
+
+// Label for a system in the UI: its configured display_name, else the uppercased folder key.
+fn system_display_name(cfg: &ConfigFile, key: &str) -> String {
+    cfg.systems
+        .as_ref()
+        .and_then(|m| m.get(key))
+        .and_then(|t| t.display_name.clone())
+        .unwrap_or_else(|| key.to_uppercase())
+}
+
+// Name shown for `rom` in the list and banner: its file name (without the extension if
+// show_extensions is off), or with show_subfolders its path below the system folder (below the
+// roms dir with group_by = "extension"), e.g. "Final Fantasy X/ffx.iso".
+fn rom_label(cfg: &ConfigFile, roms_dir: &Path, rom: &Path) -> Option<String> {
+    let name = if cfg.show_extensions.unwrap_or(true) {
+        rom.file_name()
+    } else {
+        rom.file_stem()
+    }?
+    .to_string_lossy()
+    .into_owned();
+    if !cfg.show_subfolders.unwrap_or(false) {
+        return Some(name);
+    }
+    let Some(rel_dir) = rom.strip_prefix(roms_dir).ok().and_then(Path::parent) else {
+        return Some(name);
     };
+    let skip = usize::from(cfg.group_by.as_deref() != Some("extension"));
+    let mut parts: Vec<String> = rel_dir
+        .components()
+        .skip(skip)
+        .map(|c| c.as_os_str().to_string_lossy().into_owned())
+        .collect();
+    parts.push(name);
+    Some(parts.join("/"))
+}
 
-    // scan and group roms by top-level system folder
-    let mut groups = scan_grouped(Path::new(&roms_dir), &config);
+// Entries of the settings menu, in display order. "Scrape metadata" is only offered when a
+// scraper is configured.
+fn menu_items(cfg: &ConfigFile) -> Vec<String> {
+    let mut items = vec![
+        "Toggle show_empty_systems".to_string(),
+        "Remap controller".to_string(),
+        "Remap keyboard".to_string(),
+        "Rescan ROMs".to_string(),
+        "Find duplicates".to_string(),
+    ];
+    if cfg.scraper_program.is_some() {
+        items.push("Scrape metadata".to_string());
+    }
+    items.extend([
+        "Reload config".to_string(),
+        "Save config".to_string(),
+        "Cycle theme".to_string(),
+        "Save style".to_string(),
+        "Close".to_string(),
+        "Exit".to_string(),
+    ]);
+    items
+}
 
-    // prepare systems list from config order (preserve config order if possible)
-    let mut systems_vec: Vec<String> = Vec::new();
-    if let Some(systems) = config.systems.as_ref() {
-        for k in systems.keys() {
-            let k_l = k.to_lowercase();
-            // include system if it has entries or if user wants to show empty systems
-            let has_entries = groups.get(&k_l).map(|v| !v.is_empty()).unwrap_or(false);
-            if has_entries || config.show_empty_systems.unwrap_or(false) {
-                systems_vec.push(k_l);
+// What B (controller) or Backspace does on the ROM list, from back_action. A showing error or
+// message is always dismissed first instead.
+#[derive(Clone, Copy, PartialEq)]
+enum BackAction {
+    Nothing,
+    FirstRom,
+    Menu,
+    Quit,
+}
+
+impl BackAction {
+    fn from_config(value: Option<&str>) -> BackAction {
+        match value.unwrap_or("none") {
+            "none" => BackAction::Nothing,
+            "first" => BackAction::FirstRom,
+            "menu" => BackAction::Menu,
+            "quit" => BackAction::Quit,
+            other => {
+                eprintln!(
+                    "Unknown back_action {:?}, expected none, first, menu or quit",
+                    other
+                );
+                BackAction::Nothing
             }
         }
     }
+}
 
-    if systems_vec.is_empty() {
-        eprintln!(
-            "No configured systems found in config or no systems contain ROMs. Check {}",
-            user_config_path()
-                .map(|p| p.display().to_string())
-                .unwrap_or_else(|| "~/.config/rpi_emulator_frontend/config.toml".to_string())
-        );
-    }
-
-    // current system index
-    let mut current_system_idx: usize = 0;
-    // get current system name
-    let current_system = systems_vec.get(current_system_idx).cloned();
-    // current roms list for system
-    let mut current_roms: Vec<PathBuf> = current_system
-        .as_ref()
-        .and_then(|s| groups.get(s).cloned())
-        .unwrap_or_default();
+// Actions keyboard_map can rebind, with the key each one is on by default.
+const KEY_ACTIONS: [(&str, Keycode); 8] = [
+    ("UP", Keycode::Up),
+    ("DOWN", Keycode::Down),
+    ("LEFT", Keycode::Left),
+    ("RIGHT", Keycode::Right),
+    ("A", Keycode::Return),
+    ("B", Keycode::Backspace),
+    ("START", Keycode::C),
+    ("FULLSCREEN", Keycode::W),
+];
 
-    let sdl_ctx = sdl2::init()?;
-    let video = sdl_ctx.video()?;
-    let controller_subsystem = sdl_ctx.game_controller()?;
+// keyboard_map as a key translation: a key bound to an action stands in for that action's default
+// key. Keys not mentioned keep doing what they did. Unknown actions or key names are reported.
+// Actions are taken in KEY_ACTIONS order, so a key bound twice always goes to the earlier one.
+fn key_translation(cfg: &ConfigFile) -> HashMap<Keycode, Keycode> {
+    let mut map = HashMap::new();
+    let Some(keyboard_map) = &cfg.keyboard_map else {
+        return map;
+    };
+    for action in keyboard_map.keys() {
+        if !KEY_ACTIONS
+            .iter()
+            .any(|(a, _)| a.eq_ignore_ascii_case(action))
+        {
+            eprintln!("Unknown action {:?} in keyboard_map", action);
+        }
+    }
+    for (action, default) in KEY_ACTIONS {
+        let Some(key_name) = keyboard_map
+            .iter()
+            .find(|(a, _)| a.eq_ignore_ascii_case(action))
+            .map(|(_, k)| k)
+        else {
+            continue;
+        };
+        match Keycode::from_name(key_name) {
+            Some(k) => {
+                map.entry(k).or_insert(default);
+            }
+            None => eprintln!("Unknown key {:?} for {} in keyboard_map", key_name, action),
+        }
+    }
+    map
+}
 
-    let display_mode = video.desktop_display_mode(0)?;
-    let (w, h) = (display_mode.w, display_mode.h);
+// `event` with its key replaced as `key_map` says.
+fn translate_key(mut event: Event, key_map: &HashMap<Keycode, Keycode>) -> Event {
+    if let Event::KeyDown {
+        keycode: Some(k), ..
+    }
+    | Event::KeyUp {
+        keycode: Some(k), ..
+    } = &mut event
+    {
+        if let Some(to) = key_map.get(k) {
+            *k = *to;
+        }
+    }
+    event
+}
 
-    let window = video
-        .window("RPI Frontend", w as u32, h as u32)
-        .position_centered()
-        .fullscreen() // fullscreen window
-        .build()
-        .map_err(|e| e.to_string())?;
+// The help overlay (F1 or ?): which files are in use, then the controls. Keys bound through
+// keyboard_map are listed next to the action's usual key.
+fn help_lines(
+    cfg: &ConfigFile,
+    config_path: Option<&Path>,
+    roms_dir: &str,
+    font_path: &str,
+) -> Vec<String> {
+    let shown = |p: Option<PathBuf>| {
+        p.map(|p| p.display().to_string())
+            .unwrap_or_else(|| "(none)".to_string())
+    };
+    let mut lines = vec![
+        format!("Config:  {}", shown(config_path.map(Path::to_path_buf))),
+        format!("Style:  {}", shown(style::user_style_path())),
+        format!("ROMs:  {}", roms_dir),
+        format!("Font:  {}", font_path),
+        String::new(),
+        "Keyboard".to_string(),
+    ];
+    let actions = [
+        "Up: previous ROM",
+        "Down: next ROM",
+        "Left: previous system",
+        "Right: next system",
+        "Return: launch",
+        "Backspace: back",
+        "C: menu",
+        "W: toggle fullscreen",
+    ];
+    for ((action, _), line) in KEY_ACTIONS.iter().zip(actions) {
+        let mut extra: Vec<&str> = cfg
+            .keyboard_map
+            .iter()
+            .flatten()
+            .filter(|(a, _)| a.eq_ignore_ascii_case(action))
+            .map(|(_, k)| k.as_str())
+            .collect();
+        extra.sort();
+        if extra.is_empty() {
+            lines.push(format!("  {}", line));
+        } else {
+            lines.push(format!("  {} (also {})", line, extra.join(", ")));
+        }
+    }
+    lines.extend(
+        [
+            "  PageUp/PageDown, Home/End: scroll the list",
+            "  1-9, 0: jump to a system",
+            "  I: details, E: choose emulator, O: open folder",
+            "  F1 or ?: this help",
+            "  Escape: quit",
+            "",
+            "Controller",
+            "  D-pad: move and switch systems",
+            "  A: launch, B: back, Start: menu",
+        ]
+        .map(String::from),
+    );
+    lines
+}
 
-    let mut canvas = window
-        .into_canvas()
-        .accelerated()
-        .present_vsync()
-        .build()
-        .map_err(|e| e.to_string())?;
+// How long a ROM stays blocked after `failures` quick launch failures in a row: the configured
+// cooldown once the limit is reached, doubling with every further failure.
+fn launch_cooldown(cfg: &ConfigFile, failures: u32) -> u64 {
+    let over = failures
+        .saturating_sub(cfg.launch_fail_limit.unwrap_or(3))
+        .min(6);
+    cfg.launch_fail_cooldown_secs.unwrap_or(60) << over
+}
 
-    // initialize TTF
-    let ttf_ctx: Sdl2TtfContext = sdl2::ttf::init().map_err(|e| e.to_string())?;
+// Horizontal and vertical direction (-1, 0, 1) of a hat position; up is -1.
+fn hat_direction(state: HatState) -> (i8, i8) {
+    match state {
+        HatState::Centered => (0, 0),
+        HatState::Up => (0, -1),
+        HatState::Down => (0, 1),
+        HatState::Left => (-1, 0),
+        HatState::Right => (1, 0),
+        HatState::LeftUp => (-1, -1),
+        HatState::LeftDown => (-1, 1),
+        HatState::RightUp => (1, -1),
+        HatState::RightDown => (1, 1),
+    }
+}
 
-    // try to find a reasonable system font, allow override via FONT_PATH
-    // font path preference order: config.font_path -> FONT_PATH env -> common system fonts
-    let font_path = config
-        .font_path
-        .clone()
-        .or_else(|| std::env::var("FONT_PATH").ok())
-        .or_else(|| {
-            let candidates = [
-                "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
-                "/usr/share/fonts/truetype/freefont/FreeSans.ttf",
-                "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
-            ];
-            candidates
-                .iter()
-                .find(|p| Path::new(p).exists())
-                .map(|s| s.to_string())
+// The d-pad buttons newly pressed by a hat moving from `prev` to `now`. Rolling from Up to
+// RightUp presses Right only, so a held direction doesn't repeat.
+fn hat_presses(prev: HatState, now: HatState) -> Vec<CButton> {
+    let ((px, py), (nx, ny)) = (hat_direction(prev), hat_direction(now));
+    let mut presses = Vec::new();
+    if nx != 0 && nx != px {
+        presses.push(if nx < 0 {
+            CButton::DPadLeft
+        } else {
+            CButton::DPadRight
+        });
+    }
+    if ny != 0 && ny != py {
+        presses.push(if ny < 0 {
+            CButton::DPadUp
+        } else {
+            CButton::DPadDown
         });
+    }
+    presses
+}
 
-    let font_path = match font_path {
-        Some(p) => p,
-        None => return Err("No TTF font found. Set font_path in config or install DejaVu/FreeSans or set FONT_PATH.".into()),
-    };
+// exit_chord is pressed while the emulator has focus, so pad input has to reach us in the
+// background too. SDL picks up changes to this hint at any time, so it follows config reloads.
+fn set_background_pad_input(on: bool) {
+    sdl2::hint::set(
+        "SDL_JOYSTICK_ALLOW_BACKGROUND_EVENTS",
+        if on { "1" } else { "0" },
+    );
+}
 
-    let font = ttf_ctx
-        .load_font(font_path, 14)
-        .map_err(|e| e.to_string())?;
+// Keys that repeat while held.
+const REPEAT_KEYS: [Keycode; 6] = [
+    Keycode::Up,
+    Keycode::Down,
+    Keycode::Left,
+    Keycode::Right,
+    Keycode::PageUp,
+    Keycode::PageDown,
+];
 
-    // load style/theme (writes a default style.toml in user config dir if missing)
-    let style = load_style();
-    let to_rgb = |arr: [u8; 3]| -> Color { Color::RGB(arr[0], arr[1], arr[2]) };
-    let to_rgba = |arr: [u8; 3], a: u8| -> Color { Color::RGBA(arr[0], arr[1], arr[2], a) };
-    let bg_color = to_rgb(style.background.unwrap_or([12, 12, 12]));
-    let tile_selected_c = to_rgb(style.tile_selected.unwrap_or([200, 180, 50]));
-    let tile_normal_c = to_rgb(style.tile_normal.unwrap_or([60, 60, 60]));
-    let text_primary_c = to_rgb(style.text_primary.unwrap_or([240, 240, 240]));
-    let text_secondary_c = to_rgb(style.text_secondary.unwrap_or([180, 180, 180]));
-    let banner_bg_c = to_rgb(style.banner_bg.unwrap_or([20, 20, 20]));
-    let banner_text_c = to_rgb(style.banner_text.unwrap_or([220, 220, 220]));
-    let emu_text_c = to_rgb(style.emu_text.unwrap_or([180, 180, 180]));
-    let overlay_base = style.overlay_bg.unwrap_or([0, 0, 0]);
-    let overlay_alpha = style.overlay_alpha.unwrap_or(200);
-    let overlay_rgba = to_rgba(overlay_base, overlay_alpha);
-    let menu_bg_c = to_rgb(style.menu_bg.unwrap_or([10, 10, 10]));
-    let menu_box_c = to_rgb(style.menu_box.unwrap_or([40, 40, 40]));
-    let menu_selected_c = to_rgb(style.menu_selected.unwrap_or([80, 80, 80]));
-    let menu_title_c = to_rgb(style.menu_title.unwrap_or([230, 230, 230]));
-    let menu_text_c = to_rgb(style.menu_text.unwrap_or([220, 220, 220]));
-    let message_overlay_rgba = to_rgba(
-        style.overlay_bg.unwrap_or([0, 0, 0]),
-        style.message_overlay_alpha.unwrap_or(160),
-    );
+// The app's own auto-repeat for the navigation keys, so holding one moves at the same pace
+// whatever the OS key-repeat settings are. OS repeats are dropped.
+struct KeyRepeat {
+    // the press being repeated and when its next repeat is due
+    held: Option<(Event, Instant)>,
+    delay: Duration,
+    // zero turns repeating off
+    interval: Duration,
+}
 
-    // Open controllers
-    // Keep opened controllers alive by storing them in a vector; otherwise they get dropped
-    let mut controllers: Vec<sdl2::controller::GameController> = Vec::new();
-    for id in 0..sdl_ctx.joystick()?.num_joysticks()? {
-        if controller_subsystem.is_game_controller(id) {
-            match controller_subsystem.open(id) {
-                Ok(gc) => {
-                    println!("Opened controller: {}", gc.name());
-                    controllers.push(gc);
-                }
-                Err(e) => eprintln!("Failed opening controller {}: {}", id, e),
-            }
+impl KeyRepeat {
+    fn from_config(cfg: &ConfigFile) -> KeyRepeat {
+        KeyRepeat {
+            held: None,
+            delay: Duration::from_millis(cfg.key_repeat_delay_ms.unwrap_or(400)),
+            interval: Duration::from_millis(cfg.key_repeat_interval_ms.unwrap_or(100)),
         }
     }
 
-    // channel to receive global kill requests (from X11 hotkey thread)
-    #[allow(unused_variables)]
-    let (kill_tx, kill_rx) = mpsc::channel::<()>();
-
-    // Spawn an X11 listener thread to capture a global hotkey (Ctrl+Alt+K) to kill the running emulator.
-    // This is optional: enabled with the `x11` feature. If the feature is not enabled the listener
-    // is skipped so the binary won't require X11 development libraries at link time.
-    #[cfg(feature = "x11")]
-    {
-        let kill_tx = kill_tx.clone();
-        thread::spawn(move || {
-            unsafe {
-                let display = xlib::XOpenDisplay(ptr::null());
-                if display.is_null() {
-                    eprintln!("XOpenDisplay failed, global hotkey not available");
-                    return;
-                }
-                let root = xlib::XDefaultRootWindow(display);
-                // keysym for 'K'
-                let kstr = CString::new("K").unwrap();
-                let keysym = xlib::XStringToKeysym(kstr.as_ptr());
-                if keysym == 0 {
-                    eprintln!("XStringToKeysym failed");
-                    return;
-                }
-                let keycode = xlib::XKeysymToKeycode(display, keysym as u64);
-                // grab Ctrl+Alt+K
-                let modifiers = xlib::ControlMask | xlib::Mod1Mask;
-                xlib::XGrabKey(
-                    display,
-                    keycode as i32,
-                    modifiers as u32,
-                    root,
-                    1,
-                    xlib::GrabModeAsync,
-                    xlib::GrabModeAsync,
-                );
-                xlib::XSelectInput(display, root, xlib::KeyPressMask);
-                loop {
-                    let mut ev: xlib::XEvent = std::mem::zeroed();
-                    xlib::XNextEvent(display, &mut ev);
-                    if ev.type_ == xlib::KeyPress {
-                        // send kill signal
-                        let _ = kill_tx.send(());
+    // Note presses and releases. False for an OS repeat, which the caller should drop.
+    fn observe(&mut self, event: &Event) -> bool {
+        match event {
+            Event::KeyDown { repeat: true, .. } => return false,
+            Event::KeyDown {
+                keycode: Some(k), ..
+            } if REPEAT_KEYS.contains(k) => {
+                self.held = Some((event.clone(), Instant::now() + self.delay));
+            }
+            Event::KeyUp {
+                keycode: Some(k), ..
+            } => {
+                if let Some((
+                    Event::KeyDown {
+                        keycode: Some(held),
+                        ..
+                    },
+                    _,
+                )) = &self.held
+                {
+                    if held == k {
+                        self.held = None;
                     }
                 }
             }
-        });
+            // the release may go to another window (e.g. a launched emulator)
+            Event::Window {
+                win_event: WindowEvent::FocusLost,
+                ..
+            } => self.held = None,
+            _ => {}
+        }
+        true
     }
 
-    let (tx, rx) = mpsc::channel::<()>();
+    // Another copy of the held key's press, when one is due.
+    fn due(&mut self) -> Option<Event> {
+        if self.interval.is_zero() {
+            return None;
+        }
+        let (event, next) = self.held.as_mut()?;
+        let now = Instant::now();
+        if now < *next {
+            return None;
+        }
+        *next = now + self.interval;
+        Some(event.clone())
+    }
+}
 
-    // shared slot for the running child process so we can kill it from another thread
-    let current_child: Arc<Mutex<Option<std::process::Child>>> = Arc::new(Mutex::new(None));
+// Controller buttons of a chord such as menu_chord, by SDL name ("back", "start", "leftshoulder",
+// ...). Unknown names are reported and left out.
+fn parse_chord(setting: &str, names: &[String]) -> Vec<CButton> {
+    names
+        .iter()
+        .filter_map(|n| {
+            let b = CButton::from_string(&n.to_lowercase());
+            if b.is_none() {
+                eprintln!("Unknown controller button {:?} in {}", n, setting);
+            }
+            b
+        })
+        .collect()
+}
 
-    let mut error_overlay: Option<(String, Instant)> = None;
+// The menu_chord buttons; Start alone if none are usable.
+fn configured_menu_chord(cfg: &ConfigFile) -> Vec<CButton> {
+    let chord = parse_chord("menu_chord", cfg.menu_chord.as_deref().unwrap_or(&[]));
+    if chord.is_empty() {
+        vec![CButton::Start]
+    } else {
+        chord
+    }
+}
 
-    // cache textures for filenames to avoid recreating each frame
-    let texture_creator = canvas.texture_creator();
-    // cache textures per-rom as multiple line textures (for current system)
-    let mut text_textures: Vec<Option<Vec<Texture>>> = Vec::with_capacity(current_roms.len());
-    for _ in 0..current_roms.len() {
-        text_textures.push(None);
+// How the window starts, from window_mode. W still toggles between windowed and fullscreen.
+#[derive(Clone, Copy, PartialEq)]
+enum WindowMode {
+    Fullscreen,
+    Desktop,
+    Windowed,
+    Borderless,
+}
+
+impl WindowMode {
+    fn from_config(value: Option<&str>) -> WindowMode {
+        match value.unwrap_or("fullscreen") {
+            "fullscreen" => WindowMode::Fullscreen,
+            "desktop" => WindowMode::Desktop,
+            "windowed" => WindowMode::Windowed,
+            "borderless" => WindowMode::Borderless,
+            other => {
+                eprintln!(
+                    "Unknown window_mode {:?}, expected fullscreen, desktop, windowed or borderless",
+                    other
+                );
+                WindowMode::Fullscreen
+            }
+        }
     }
+}
 
-    let mut event_pump = sdl_ctx.event_pump()?;
-    let mut selected: usize = 0;
-    let mut scroll_offset: usize = 0;
-    let mut launching = false;
-    let mut is_fullscreen = true;
-    // menu state
-    #[derive(PartialEq)]
-    enum MenuState {
-        Closed,
-        Open {
-            items: Vec<String>,
-            selected: usize,
-        },
-        Remap {
-            actions: Vec<String>,
-            idx: usize,
-            temp_map: HashMap<String, String>,
-        },
+// List entries for the two_level system list: one per system, its folder under the roms dir.
+// Only their position matters; tiles are labelled from `systems_vec`.
+fn system_entries(roms_dir: &str, systems_vec: &[String]) -> Vec<PathBuf> {
+    systems_vec
+        .iter()
+        .map(|s| Path::new(roms_dir).join(s))
+        .collect()
+}
+
+// Number of ROM entries per system, computed once per scan.
+fn rom_counts(groups: &HashMap<String, Vec<PathBuf>>) -> HashMap<String, usize> {
+    groups.iter().map(|(k, v)| (k.clone(), v.len())).collect()
+}
+
+// Systems shown in the UI: configured systems that have ROMs (or all of them when
+// show_empty_systems is set), except hidden ones.
+fn build_systems_vec(cfg: &ConfigFile, counts: &HashMap<String, usize>) -> Vec<String> {
+    let mut systems_vec: Vec<String> = Vec::new();
+    if let Some(systems) = cfg.systems.as_ref() {
+        for (k, tmpl) in systems.iter() {
+            if tmpl.hidden.unwrap_or(false) {
+                continue;
+            }
+            let k_l = k.to_lowercase();
+            // include system if it has entries or if user wants to show empty systems
+            let has_entries = counts.get(&k_l).copied().unwrap_or(0) > 0;
+            if has_entries || cfg.show_empty_systems.unwrap_or(false) {
+                systems_vec.push(k_l);
+            }
+        }
     }
-    let mut menu_state = MenuState::Closed;
-    let mut menu_message: Option<(String, Instant)> = None;
-    let mut should_quit = false;
+    match cfg.system_sort.as_deref().unwrap_or("config") {
+        "alpha" => systems_vec.sort(),
+        // most ROMs first, ties alphabetically
+        "count" => systems_vec.sort_by(|a, b| {
+            let count = |k: &String| counts.get(k).copied().unwrap_or(0);
+            count(b).cmp(&count(a)).then_with(|| a.cmp(b))
+        }),
+        other => {
+            if other != "config" {
+                eprintln!(
+                    "Unknown system_sort {:?}, expected config, alpha or count",
+                    other
+                );
+            }
+            order_systems(&mut systems_vec, cfg.system_order.as_deref().unwrap_or(&[]));
+        }
+    }
+    systems_vec
+}
 
-    'running: loop {
-        // handle spawn completion
-        if let Ok(_) = rx.try_recv() {
-            launching = false;
+// Sort systems so the ones named in `order` come first, in that order; the rest follow
+// alphabetically. The systems map is a HashMap, so without this the order changes every run.
+fn order_systems(systems: &mut [String], order: &[String]) {
+    let rank = |k: &String| {
+        order
+            .iter()
+            .position(|o| o.eq_ignore_ascii_case(k))
+            .unwrap_or(order.len())
+    };
+    systems.sort_by(|a, b| rank(a).cmp(&rank(b)).then_with(|| a.cmp(b)));
+}
+
+// Index of the system after `cur` in a list of `len` systems, wrapping around.
+// None when there are no systems (e.g. an empty roms dir) so callers can no-op.
+fn next_system_idx(cur: usize, len: usize) -> Option<usize> {
+    if len == 0 {
+        None
+    } else {
+        Some((cur + 1) % len)
+    }
+}
+
+// Index of the system before `cur`, wrapping around; None when there are no systems.
+fn prev_system_idx(cur: usize, len: usize) -> Option<usize> {
+    if len == 0 {
+        None
+    } else if cur == 0 || cur >= len {
+        Some(len - 1)
+    } else {
+        Some(cur - 1)
+    }
+}
+
+// Move the selection by `delta` rows, clamped to the list, and return the new
+// (selected, scroll_offset) with the scroll adjusted so the selection stays on screen.
+fn move_selection(
+    selected: usize,
+    scroll_offset: usize,
+    len: usize,
+    visible: usize,
+    delta: isize,
+) -> (usize, usize) {
+    if len == 0 {
+        return (0, 0);
+    }
+    let sel = selected.saturating_add_signed(delta).min(len - 1);
+    let scroll = if sel < scroll_offset {
+        sel
+    } else if sel >= scroll_offset + visible {
+        sel + 1 - visible.max(1)
+    } else {
+        scroll_offset
+    };
+    (sel, scroll)
+}
+
+// Index of the list row at height `py` for tiles laid out from `start_y`, or None when `py` is in
+// the gap between tiles or past the end of the list.
+fn tile_at(
+    py: i32,
+    start_y: i32,
+    tile_h: i32,
+    padding: i32,
+    scroll_offset: usize,
+    len: usize,
+) -> Option<usize> {
+    if py < start_y {
+        return None;
+    }
+    let row_h = tile_h + padding;
+    if (py - start_y) % row_h >= tile_h {
+        return None;
+    }
+    let idx = scroll_offset + ((py - start_y) / row_h) as usize;
+    if idx < len {
+        Some(idx)
+    } else {
+        None
+    }
+}
+
+// Rows whose cached textures to drop so at most `cap` rows stay cached: those farthest from the
+// visible rows `first..first + visible` go first. Visible rows are never evicted.
+fn rows_to_evict(cached: &[usize], first: usize, visible: usize, cap: usize) -> Vec<usize> {
+    let cap = cap.max(visible);
+    if cached.len() <= cap {
+        return Vec::new();
+    }
+    let distance = |row: usize| -> usize {
+        if row < first {
+            first - row
+        } else {
+            row.saturating_sub(first + visible - 1)
+        }
+    };
+    let mut by_distance: Vec<usize> = cached.to_vec();
+    by_distance.sort_by_key(|&row| std::cmp::Reverse(distance(row)));
+    by_distance.truncate(cached.len() - cap);
+    by_distance
+}
+
+// Horizontal scroll for the system tab bar so the `current` tab is fully visible in `view_w`,
+// moving as little as possible from the previous `scroll`.
+fn tab_scroll(widths: &[i32], current: usize, view_w: i32, scroll: i32) -> i32 {
+    let left: i32 = widths.iter().take(current).sum();
+    let right = left + widths.get(current).copied().unwrap_or(0);
+    let total: i32 = widths.iter().sum();
+    let scroll = if left < scroll {
+        left
+    } else if right > scroll + view_w {
+        right - view_w
+    } else {
+        scroll
+    };
+    scroll.min((total - view_w).max(0)).max(0)
+}
+
+// Range of items [lo, hi) around `current` that fits in `max_w` when laid out with `gap` px
+// between them, growing to the right and left in turn so the current item stays near the middle.
+fn overview_window(widths: &[i32], current: usize, gap: i32, max_w: i32) -> (usize, usize) {
+    if current >= widths.len() {
+        return (0, 0);
+    }
+    let (mut lo, mut hi) = (current, current + 1);
+    let mut used = widths[current];
+    loop {
+        let mut grew = false;
+        if hi < widths.len() && used + gap + widths[hi] <= max_w {
+            used += gap + widths[hi];
+            hi += 1;
+            grew = true;
+        }
+        if lo > 0 && used + gap + widths[lo - 1] <= max_w {
+            used += gap + widths[lo - 1];
+            lo -= 1;
+            grew = true;
+        }
+        if !grew {
+            return (lo, hi);
+        }
+    }
+}
+
+// Offset and height of the scrollbar thumb within a track of `track_h` px for a list of `total`
+// rows showing `visible` rows from `offset`. None when every row fits on screen.
+fn scrollbar_thumb(
+    track_h: i32,
+    total: usize,
+    visible: usize,
+    offset: usize,
+) -> Option<(i32, i32)> {
+    if total <= visible || track_h <= 0 {
+        return None;
+    }
+    let thumb_h =
+        ((track_h as i64 * visible as i64 / total as i64) as i32).clamp(12.min(track_h), track_h);
+    let max_offset = (total - visible) as i64;
+    let thumb_y =
+        ((track_h - thumb_h) as i64 * (offset as i64).min(max_offset) / max_offset) as i32;
+    Some((thumb_y, thumb_h))
+}
+
+// A rendered line of text that is only re-rendered when its string or color changes, for labels
+// drawn every frame (the banner).
+struct CachedText<'a> {
+    text: String,
+    color: Color,
+    texture: Option<Texture<'a>>,
+}
+
+impl<'a> CachedText<'a> {
+    fn new() -> Self {
+        CachedText {
+            text: String::new(),
+            color: Color::RGB(0, 0, 0),
+            texture: None,
+        }
+    }
+
+    // Texture for `text` in `color`, rendering it only if it differs from the last call.
+    fn get(
+        &mut self,
+        font: &sdl2::ttf::Font,
+        texture_creator: &'a sdl2::render::TextureCreator<sdl2::video::WindowContext>,
+        text: &str,
+        color: Color,
+    ) -> Option<&Texture<'a>> {
+        if self.texture.is_none() || self.text != text || self.color != color {
+            self.texture = font
+                .render(text)
+                .blended(color)
+                .ok()
+                .and_then(|surf| texture_creator.create_texture_from_surface(&surf).ok());
+            self.text = text.to_string();
+            self.color = color;
+        }
+        self.texture.as_ref()
+    }
+}
+
+// how far a stick must be pushed to count as a navigation press
+const AXIS_THRESHOLD: i16 = 16000;
+
+// Which way an axis `value` is pushed: -1, 1, or 0 when within AXIS_THRESHOLD of the center.
+fn axis_direction(value: i16) -> i8 {
+    if value < -AXIS_THRESHOLD {
+        -1
+    } else if value > AXIS_THRESHOLD {
+        1
+    } else {
+        0
+    }
+}
+
+// Input from the user (as opposed to window or device events), which wakes the attract mode.
+// Sticks count once pushed as far as navigation needs, so drift doesn't keep the screen awake.
+fn is_user_input(event: &Event) -> bool {
+    match event {
+        Event::JoyAxisMotion { value, .. } | Event::ControllerAxisMotion { value, .. } => {
+            axis_direction(*value) != 0
+        }
+        _ => matches!(
+            event,
+            Event::KeyDown { .. }
+                | Event::ControllerButtonDown { .. }
+                | Event::JoyButtonDown { .. }
+                | Event::MouseButtonDown { .. }
+                | Event::MouseWheel { .. }
+                | Event::FingerDown { .. }
+        ),
+    }
+}
+
+// Fill `rect` with the current draw color, rounding its corners to `radius` (0 = square). The
+// corners are drawn as one 1px-high span per row, which is cheap enough to do every frame.
+fn fill_rounded_rect(
+    canvas: &mut sdl2::render::Canvas<sdl2::video::Window>,
+    rect: Rect,
+    radius: i32,
+) {
+    let (x, y, w, h) = (
+        rect.x(),
+        rect.y(),
+        rect.width() as i32,
+        rect.height() as i32,
+    );
+    let r = radius.min(w / 2).min(h / 2);
+    if r <= 0 {
+        let _ = canvas.fill_rect(rect);
+        return;
+    }
+    let mut spans: Vec<Rect> = Vec::with_capacity(2 * r as usize + 1);
+    if h > 2 * r {
+        spans.push(Rect::new(x, y + r, w as u32, (h - 2 * r) as u32));
+    }
+    for row in 0..r {
+        // horizontal distance from the tile edge to the corner arc at this row
+        let dy = (r - row) as f32 - 0.5;
+        let inset = r - ((r * r) as f32 - dy * dy).max(0.0).sqrt().round() as i32;
+        let span_w = (w - 2 * inset).max(0) as u32;
+        spans.push(Rect::new(x + inset, y + row, span_w, 1));
+        spans.push(Rect::new(x + inset, y + h - 1 - row, span_w, 1));
+    }
+    let _ = canvas.fill_rects(&spans);
+}
+
+// Fire-and-forget rumble on every opened controller. Pads without rumble support return an
+// error, which is ignored.
+fn rumble_all(controllers: &mut [sdl2::controller::GameController], strength: u16, ms: u32) {
+    for c in controllers.iter_mut() {
+        let _ = c.set_rumble(strength, strength, ms);
+    }
+}
+
+fn main() -> Result<(), String> {
+    let cli = match cli::parse_args(env::args().skip(1)) {
+        Ok(cli::CliAction::Run(args)) => args,
+        Ok(cli::CliAction::Help) => {
+            println!("{}", cli::USAGE);
+            return Ok(());
+        }
+        Ok(cli::CliAction::Version) => {
+            println!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"));
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    // load config (writes default sample if needed); --config wins over RPI_FRONTEND_CONFIG, which
+    // wins over the XDG default. Saving from the menu writes back to the same file.
+    let config_path = cli
+        .config
+        .clone()
+        .or_else(|| {
+            env::var("RPI_FRONTEND_CONFIG")
+                .ok()
+                .filter(|p| !p.is_empty())
+                .map(|p| PathBuf::from(paths::expand_path(&p)))
+        })
+        .or_else(user_config_path);
+
+    // --reset-config: recovery from a broken config or style
+    if cli.reset_config {
+        let paths = [
+            (config_path.clone(), write_default_config as fn(&Path) -> _),
+            (style::user_style_path(), style::write_default_style),
+        ];
+        for (path, write_default) in paths {
+            match path {
+                Some(p) => println!("{}", reset_to_default(&p, write_default)?),
+                None => return Err("No config path available".to_string()),
+            }
+        }
+        return Ok(());
+    }
+
+    let Loaded {
+        value: mut config,
+        created: config_created,
+        parse_error: config_error,
+    } = load_config(config_path.as_deref());
+
+    // determine roms dir: prefer CLI arg, else config.default_roms_path, else ./roms
+    let roms_dir = match cli.roms.clone() {
+        Some(d) => d,
+        None => config
+            .default_roms_path
+            .as_deref()
+            .map(paths::expand_path)
+            .unwrap_or_else(|| "./roms".to_string()),
+    };
+
+    // systems whose emulator binary is missing (dimmed in the list, refused on launch)
+    let mut missing_emulators = missing_emulator_systems(&config);
+
+    // scan and group roms by top-level system folder
+    let (mut groups, mut roms_with_meta, mut scan_errors) =
+        scan_grouped(Path::new(&roms_dir), &config);
+    // first disc -> all discs of each merged multi-disc game
+    let mut disc_sets = if config.merge_discs.unwrap_or(false) {
+        merge_disc_sets(&mut groups)
+    } else {
+        HashMap::new()
+    };
+
+    let mut system_counts = rom_counts(&groups);
+
+    // prepare systems list from config order (preserve config order if possible)
+    let mut systems_vec: Vec<String> = build_systems_vec(&config, &system_counts);
+
+    let config_path_display = config_path
+        .as_ref()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "~/.config/rpi_emulator_frontend/config.toml".to_string());
+    if systems_vec.is_empty() {
+        eprintln!(
+            "No configured systems found in config or no systems contain ROMs. Check {}",
+            config_path_display
+        );
+    }
+
+    // --list: print what would be shown and stop before opening a window
+    if cli.list {
+        for sys in &systems_vec {
+            let roms = groups.get(sys).map(Vec::as_slice).unwrap_or_default();
+            println!("{} ({})", system_display_name(&config, sys), roms.len());
+            for rom in roms {
+                let label = rom_label(&config, Path::new(&roms_dir), rom);
+                println!("  {}", label.unwrap_or_default());
+            }
+        }
+        let unconfigured = unconfigured_folders(Path::new(&roms_dir), &config);
+        if !unconfigured.is_empty() {
+            println!("\nFolders with no [systems] entry (add one to show them):");
+            for f in &unconfigured {
+                println!(
+                    "  {} ({} files: {})",
+                    f.name,
+                    f.files,
+                    f.extensions.join(", ")
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    // current system index
+    let mut current_system_idx: usize = 0;
+    // get current system name
+    let current_system = systems_vec.get(current_system_idx).cloned();
+    // navigation = "two_level" starts on a list of systems; A enters one, B goes back up
+    let two_level = match config.navigation.as_deref().unwrap_or("flat") {
+        "two_level" => true,
+        "flat" => false,
+        other => {
+            eprintln!("Unknown navigation {:?}, expected flat or two_level", other);
+            false
+        }
+    };
+    let mut at_system_list = two_level;
+    // current roms list for system (the systems themselves on the two_level system list)
+    let mut current_roms: Vec<PathBuf> = if at_system_list {
+        system_entries(&roms_dir, &systems_vec)
+    } else {
+        current_system
+            .as_ref()
+            .and_then(|s| groups.get(s).cloned())
+            .unwrap_or_default()
+    };
+
+    set_background_pad_input(config.exit_chord.is_some());
+    let sdl_ctx = sdl2::init()?;
+    let video = sdl_ctx.video()?;
+    let controller_subsystem = sdl_ctx.game_controller()?;
+
+    // display_index picks the monitor; an index that is not connected falls back to the first
+    let num_displays = video.num_video_displays()?;
+    let display = match config.display_index.unwrap_or(0) {
+        i if (0..num_displays).contains(&i) => i,
+        i => {
+            eprintln!(
+                "display_index {} out of range ({} display(s)), using display 0",
+                i, num_displays
+            );
+            0
+        }
+    };
+    let display_mode = video.desktop_display_mode(display)?;
+    let display_bounds = video.display_bounds(display)?;
+    let window_mode = WindowMode::from_config(config.window_mode.as_deref());
+    // the layout is computed once for this size, so it is also the size of a non-fullscreen window
+    let (w, h) = match config.window_size {
+        Some([ww, wh]) if ww > 0 && wh > 0 => (ww as i32, wh as i32),
+        _ => (display_mode.w, display_mode.h),
+    };
+
+    let mut window_builder = video.window("RPI Frontend", w as u32, h as u32);
+    window_builder.position(
+        display_bounds.x() + (display_bounds.width() as i32 - w) / 2,
+        display_bounds.y() + (display_bounds.height() as i32 - h) / 2,
+    );
+    match window_mode {
+        WindowMode::Fullscreen => window_builder.fullscreen(),
+        WindowMode::Desktop => window_builder.fullscreen_desktop(),
+        WindowMode::Windowed => &mut window_builder,
+        WindowMode::Borderless => window_builder.borderless(),
+    };
+    let window = window_builder.build().map_err(|e| e.to_string())?;
+
+    let mut canvas = window
+        .into_canvas()
+        .accelerated()
+        .present_vsync()
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    // marquee_display: a second window showing art for the selection
+    let mut marquee_canvas = match config.marquee_display {
+        Some(d) if d == display => {
+            eprintln!(
+                "marquee_display {} is the main display, not opening the marquee",
+                d
+            );
+            None
+        }
+        d => d.and_then(|d| marquee::open(&video, d)),
+    };
+    let marquee_texture_creator = marquee_canvas.as_ref().map(|c| c.texture_creator());
+
+    // initialize TTF
+    let ttf_ctx: Sdl2TtfContext = sdl2::ttf::init().map_err(|e| e.to_string())?;
+
+    // try to find a reasonable system font, allow override via FONT_PATH
+    // font path preference order: config.font_path -> FONT_PATH env -> common system fonts
+    let font_path = config
+        .font_path
+        .as_deref()
+        .map(paths::expand_path)
+        .or_else(|| std::env::var("FONT_PATH").ok())
+        .or_else(|| {
+            let candidates = [
+                "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+                "/usr/share/fonts/truetype/freefont/FreeSans.ttf",
+                "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
+            ];
+            candidates
+                .iter()
+                .find(|p| Path::new(p).exists())
+                .map(|s| s.to_string())
+        });
+
+    let font_path = match font_path {
+        Some(p) => p,
+        None => return Err("No TTF font found. Set font_path in config or install DejaVu/FreeSans or set FONT_PATH.".into()),
+    };
+
+    let accessibility = config.accessibility.unwrap_or(false);
+    let font_size = if accessibility {
+        ACCESSIBLE_FONT_SIZE
+    } else {
+        FONT_SIZE
+    };
+    let font = ttf_ctx
+        .load_font(&font_path, font_size)
+        .map_err(|e| e.to_string())?;
+
+    // load style/theme (writes a default style.toml in user config dir if missing)
+    let Loaded {
+        value: mut style,
+        parse_error: style_error,
+        ..
+    } = style::load_style();
+    // what "Save style" writes: the file's style without the accessibility overrides
+    let mut saved_style = style.clone();
+    if accessibility {
+        style::apply_accessibility(&mut style);
+    }
+    let max_title_lines = style.max_title_lines.unwrap_or(2).clamp(1, 3) as usize;
+    let tile_h_cfg = style
+        .tile_height
+        .map(|v| v.min(i32::MAX as u32) as i32)
+        .unwrap_or(TILE_H)
+        .max(MIN_TILE_H);
+    // tiles grow when more title lines are configured than fit the configured height
+    let tile_h = tile_h_cfg.max(max_title_lines as i32 * (font.height() + 2) + 16);
+    let selection_border = style
+        .selection_border
+        .map(|v| v.min(tile_h as u32 / 2) as i32)
+        .unwrap_or(0);
+    // selected tile pulse: pulses per second, or None when off
+    let selection_pulse = if style.selection_pulse.unwrap_or(false) {
+        Some(style.selection_pulse_speed.unwrap_or(1.0).max(0.0))
+    } else {
+        None
+    };
+    let pulse_start = Instant::now();
+    // tile shape and border
+    let tile_corner_radius = style.tile_corner_radius.unwrap_or(0).min(tile_h as u32 / 2) as i32;
+    let tile_border_width = style
+        .tile_border_width
+        .map(|v| v.min(tile_h as u32 / 2) as i32)
+        .unwrap_or(0);
+    // colors for drawing; re-resolved when the theme is changed from the menu
+    let mut palette = style::Palette::from_style(&style);
+    // index into style::PRESETS of the last theme picked from the menu
+    let mut theme_idx: usize = 0;
+
+    // Load community controller mappings (gamecontrollerdb.txt) before opening controllers so
+    // they apply to pads SDL doesn't know about out of the box.
+    if let Some(db) = config.controller_db_path.as_deref().map(paths::expand_path) {
+        match controller_subsystem.load_mappings(&db) {
+            Ok(n) => println!("Loaded {} controller mappings from {}", n, db),
+            Err(e) => eprintln!("Failed loading controller mappings from {}: {}", db, e),
+        }
+    }
+
+    // Open controllers
+    // Keep opened controllers alive by storing them in a vector; otherwise they get dropped
+    let mut controllers: Vec<sdl2::controller::GameController> = Vec::new();
+    // pads SDL has no controller mapping for are opened as plain joysticks, for their buttons
+    // and d-pad hats
+    let joystick_subsystem = sdl_ctx.joystick()?;
+    let mut joysticks: Vec<sdl2::joystick::Joystick> = Vec::new();
+    for id in 0..joystick_subsystem.num_joysticks()? {
+        if controller_subsystem.is_game_controller(id) {
+            match controller_subsystem.open(id) {
+                Ok(gc) => {
+                    println!("Opened controller: {}", gc.name());
+                    controllers.push(gc);
+                }
+                Err(e) => eprintln!("Failed opening controller {}: {}", id, e),
+            }
+        } else {
+            match joystick_subsystem.open(id) {
+                Ok(js) => {
+                    println!("Opened joystick: {}", js.name());
+                    joysticks.push(js);
+                }
+                Err(e) => eprintln!("Failed opening joystick {}: {}", id, e),
+            }
+        }
+    }
+
+    // commands from the optional control socket
+    #[cfg(unix)]
+    let (control_tx, control_rx) = mpsc::channel::<control::ControlRequest>();
+    #[cfg(unix)]
+    if let Some(sock) = config.control_socket.as_deref().map(paths::expand_path) {
+        match control::listen(Path::new(&sock), control_tx) {
+            Ok(()) => println!("Listening for control commands on {}", sock),
+            Err(e) => eprintln!("Failed to open control socket {}: {}", sock, e),
         }
+    }
+
+    // channel to receive global kill requests (from X11 hotkey thread)
+    #[allow(unused_variables)]
+    let (kill_tx, kill_rx) = mpsc::channel::<()>();
+
+    // Spawn an X11 listener thread to capture a global hotkey (Ctrl+Alt+K) to kill the running emulator.
+    // This is optional: enabled with the `x11` feature. If the feature is not enabled the listener
+    // is skipped so the binary won't require X11 development libraries at link time.
+    #[cfg(feature = "x11")]
+    {
+        let kill_tx = kill_tx.clone();
+        thread::spawn(move || {
+            unsafe {
+                let display = xlib::XOpenDisplay(ptr::null());
+                if display.is_null() {
+                    eprintln!("XOpenDisplay failed, global hotkey not available");
+                    return;
+                }
+                let root = xlib::XDefaultRootWindow(display);
+                // keysym for 'K'
+                let kstr = CString::new("K").unwrap();
+                let keysym = xlib::XStringToKeysym(kstr.as_ptr());
+                if keysym == 0 {
+                    eprintln!("XStringToKeysym failed");
+                    return;
+                }
+                let keycode = xlib::XKeysymToKeycode(display, keysym as u64);
+                // grab Ctrl+Alt+K
+                let modifiers = xlib::ControlMask | xlib::Mod1Mask;
+                xlib::XGrabKey(
+                    display,
+                    keycode as i32,
+                    modifiers as u32,
+                    root,
+                    1,
+                    xlib::GrabModeAsync,
+                    xlib::GrabModeAsync,
+                );
+                xlib::XSelectInput(display, root, xlib::KeyPressMask);
+                loop {
+                    let mut ev: xlib::XEvent = std::mem::zeroed();
+                    xlib::XNextEvent(display, &mut ev);
+                    if ev.type_ == xlib::KeyPress {
+                        // send kill signal
+                        let _ = kill_tx.send(());
+                    }
+                }
+            }
+        });
+    }
+
+    // completion channel for launch threads; carries an error message on nonzero emulator exit
+    let (tx, rx) = mpsc::channel::<Result<(), String>>();
+
+    // progress from the scraper thread; `scrape_status` is the latest line while one is running
+    let (scrape_tx, scrape_rx) = mpsc::channel::<scrape::ScrapeProgress>();
+    let mut scrape_status: Option<String> = None;
+    // result of a "Find duplicates" search running in the background
+    let (dupes_tx, dupes_rx) = mpsc::channel::<Vec<dupes::DuplicateSet>>();
+    let mut dupes_running = false;
+
+    // shared slot for the running child process so we can kill it from another thread
+    let current_child: Arc<Mutex<Option<std::process::Child>>> = Arc::new(Mutex::new(None));
+
+    // a config or style that failed to parse, or unreadable ROM folders, are reported on startup
+    let mut error_overlay: Option<(String, Instant)> = config_error
+        .or(style_error)
+        .or_else(|| scan_error_message(&scan_errors))
+        .map(|m| (m, Instant::now()));
+
+    // cache textures for filenames to avoid recreating each frame
+    let texture_creator = canvas.texture_creator();
+    // cache textures per-rom as multiple line textures (for current system)
+    let mut text_textures: Vec<Option<Vec<Texture>>> = Vec::with_capacity(current_roms.len());
+    // attract mode after attract_timeout_secs without input: "dim" darkens the screen,
+    // "slideshow" shows random ROM names; the input that wakes it is otherwise ignored
+    let back_action = BackAction::from_config(config.back_action.as_deref());
+    let attract_mode = match config.attract_mode.as_deref().unwrap_or("off") {
+        mode @ ("dim" | "slideshow") => Some(mode.to_string()),
+        "off" => None,
+        other => {
+            eprintln!(
+                "Unknown attract_mode {:?}, expected dim, slideshow or off",
+                other
+            );
+            None
+        }
+    };
+    let attract_timeout = config.attract_timeout_secs.unwrap_or(300);
+    let mut last_input = Instant::now();
+    // when attract mode started, while it is showing
+    let mut attract_since: Option<Instant> = None;
+    // picks the slideshow ROMs; randomly seeded per run
+    let slide_hasher = std::collections::hash_map::RandomState::new();
+    let mut attract_name_text = CachedText::new();
+    let mut attract_system_text = CachedText::new();
+    // on-screen volume control through amixer; the bar shows the level after each change
+    let volume_control = config.volume_control.unwrap_or(false);
+    let volume_mixer = config
+        .volume_mixer
+        .clone()
+        .unwrap_or_else(|| "Master".to_string());
+    let mut volume_osd: Option<(u8, Instant)> = None;
+    // marquee art comes from marquee_art_dir, by default `art` next to the config file in use;
+    // with neither there is no art, just the names
+    let marquee_art_dir: Option<PathBuf> = config
+        .marquee_art_dir
+        .as_deref()
+        .map(|d| PathBuf::from(paths::expand_path(d)))
+        .or_else(|| {
+            config_path
+                .as_deref()
+                .and_then(Path::parent)
+                .map(|d| d.join("art"))
+        });
+    // the selection (system, ROM) the marquee art was loaded for, and that art
+    let mut marquee_shown: Option<(String, Option<PathBuf>)> = None;
+    let mut marquee_art: Option<Texture> = None;
+    let mut marquee_system_text = CachedText::new();
+    let mut marquee_rom_text = CachedText::new();
+    // buttons held together (for menu_chord_hold_ms) to open the menu; a plain Start press keeps
+    // working through the button events when that is all the chord is
+    let mut menu_chord = configured_menu_chord(&config);
+    let mut menu_chord_hold_ms = config.menu_chord_hold_ms.unwrap_or(0);
+    let mut start_opens_menu = menu_chord == [CButton::Start] && menu_chord_hold_ms == 0;
+    // when every chord button was first seen held, and whether it already opened the menu
+    let mut menu_chord_held: Option<(Instant, bool)> = None;
+    // keyboard_map, as keys to treat as other keys
+    let mut key_map = key_translation(&config);
+    let mut key_repeat = KeyRepeat::from_config(&config);
+    // controller buttons that kill the running emulator when held together (empty: off)
+    let mut exit_chord = parse_chord("exit_chord", config.exit_chord.as_deref().unwrap_or(&[]));
+    // set once exit_chord has fired, until one of its buttons is let go
+    let mut exit_chord_fired = false;
+    // banner labels, re-rendered only when their text changes
+    let mut banner_system_text = CachedText::new();
+    let mut banner_rom_text = CachedText::new();
+    let mut banner_emu_text = CachedText::new();
+    for _ in 0..current_roms.len() {
+        text_textures.push(None);
+    }
+
+    let mut event_pump = sdl_ctx.event_pump()?;
+    let mut selected: usize = 0;
+    let mut scroll_offset: usize = 0;
+    let mut launching = false;
+    // when the current launch started; cleared once the emulator takes focus or the watchdog fires
+    let mut launch_watch: Option<Instant> = None;
+    // hide_on_launch hid the window for the running emulator
+    let mut window_hidden = false;
+    // the ROM being played and when it started
+    let mut playing: Option<(PathBuf, Instant)> = None;
+    // the last position of each joystick hat, by (joystick, hat)
+    let mut hat_states: HashMap<(u32, u8), HatState> = HashMap::new();
+    // the direction (-1, 0, 1) each joystick axis was last pushed, by (joystick, axis)
+    let mut axis_dirs: HashMap<(u32, u8), i8> = HashMap::new();
+    // quick launch failures in a row per ROM, and when the last one happened
+    let mut launch_failures: HashMap<PathBuf, (u32, Instant)> = HashMap::new();
+    let mut is_fullscreen = matches!(window_mode, WindowMode::Fullscreen | WindowMode::Desktop);
+    // what W switches back to: the configured fullscreen kind, else fullscreen at desktop size
+    let fullscreen_type = if window_mode == WindowMode::Fullscreen {
+        FullscreenType::True
+    } else {
+        FullscreenType::Desktop
+    };
+    // menu state
+    #[derive(PartialEq)]
+    enum MenuState {
+        Closed,
+        Open {
+            items: Vec<String>,
+            selected: usize,
+        },
+        // captures controller buttons into controller_map, or keys into keyboard_map
+        Remap {
+            actions: Vec<String>,
+            idx: usize,
+            temp_map: HashMap<String, String>,
+            keyboard: bool,
+        },
+        DiscSelect {
+            template: Box<CmdTemplate>,
+            discs: Vec<PathBuf>,
+            selected: usize,
+        },
+        // yes/no question; `options` are the labels for yes and no
+        Confirm {
+            title: String,
+            options: [&'static str; 2],
+            action: ConfirmAction,
+            selected: usize,
+        },
+        // pick the default or an alternate emulator for one ROM
+        EmulatorSelect {
+            rom: PathBuf,
+            choices: Vec<CmdTemplate>,
+            selected: usize,
+        },
+        // details for one ROM: its metadata file if it has one, plus file facts
+        Detail {
+            rom: PathBuf,
+            title: String,
+            lines: Vec<String>,
+        },
+        // read-only list too long for one screen (e.g. the duplicates report); `scroll` is the
+        // first line shown
+        Report {
+            title: String,
+            lines: Vec<String>,
+            scroll: usize,
+        },
+    }
+    // what answering yes to a Confirm prompt does
+    #[derive(PartialEq)]
+    enum ConfirmAction {
+        // the emulator never took focus
+        KillEmulator,
+        // confirm_launch is on
+        Launch(Box<CmdTemplate>, PathBuf),
+    }
+    // the confirm_launch prompt for starting `rom` with `t`
+    fn launch_confirm(t: CmdTemplate, rom: PathBuf) -> MenuState {
+        let name = rom
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("")
+            .to_string();
+        MenuState::Confirm {
+            title: t
+                .confirm_message
+                .clone()
+                .unwrap_or_else(|| format!("Launch {}?", name)),
+            options: ["Launch", "Cancel"],
+            action: ConfirmAction::Launch(Box::new(t), rom),
+            selected: 0,
+        }
+    }
+    // first run: say where the new config is, since nothing shows until systems are added
+    let mut menu_state = match config_path.as_ref().filter(|_| config_created) {
+        Some(p) => MenuState::Report {
+            title: "Welcome! A config file was created".to_string(),
+            lines: vec![
+                "It was written to:".to_string(),
+                format!("  {}", p.display()),
+                String::new(),
+                "Edit it to add a [systems] entry for each folder in your".to_string(),
+                format!("roms dir ({}), then pick \"Reload config\"", roms_dir),
+                "from the menu (Start or C).".to_string(),
+                String::new(),
+                "Press A or Enter to continue.".to_string(),
+            ],
+            scroll: 0,
+        },
+        None => MenuState::Closed,
+    };
+    let mut menu_message: Option<(String, Instant)> = None;
+    let mut should_quit = false;
+    // resolved launch waiting to start; set by a launch request or the emulator picker
+    let mut pending_launch: Option<(CmdTemplate, PathBuf)> = None;
+    // launch ready to start (disc already chosen); picked up before the next render
+    let mut ready_launch: Option<(CmdTemplate, PathBuf)> = None;
+    // ROM the user asked to launch (from keyboard, controller, joystick or the detail view);
+    // resolved the same frame, or the next one when set while an overlay is drawn
+    let mut launch_request: Option<PathBuf> = None;
+    // re-scan ROMs before the next frame; Some(true) re-reads the config first
+    let mut rescan_request: Option<bool> = None;
+    // mouse/touch press in progress: where it started, vertical drag not yet turned into
+    // scrolling, and whether it has scrolled (a press that scrolled is not a tap)
+    let mut press: Option<(i32, i32)> = None;
+    let mut drag_acc: i32 = 0;
+    let mut dragged = false;
+    // horizontal scroll of the system tab bar, kept between frames so it only moves when needed
+    let mut tabs_scroll: i32 = 0;
+
+    // banner: style banner_height, grown to fit the font
+    let banner_h = style
+        .banner_height
+        .map(|v| v.min(400) as i32)
+        .unwrap_or(40)
+        .max(font.height() + 8);
+    let filename_left = style.banner_filename_align.as_deref() == Some("left");
+
+    // list layout (single column). compute tile sizes and visible window; shared by input
+    // handling (scrolling/paging) and rendering
+    let padding = style
+        .tile_padding
+        .map(|v| v.min(200) as i32)
+        .unwrap_or(TILE_PADDING);
+    // tiles span the window, or at most max_content_width with the list centered
+    let tile_w = match config.max_content_width {
+        Some(max) => (w - padding * 2).min(max.min(i32::MAX as u32) as i32),
+        None => w - (padding * 2),
+    };
+    let start_x = (w - tile_w) / 2;
+    // optional system tab bar sits between the banner and the list
+    let tabs_top = banner_h + 4;
+    let tabs_h = if config.show_system_tabs.unwrap_or(false) {
+        TAB_BAR_H
+    } else {
+        0
+    };
+    // optional one-line overview of every system and its ROM count, below the tabs
+    let overview_top = tabs_top + tabs_h;
+    let overview_h = if config.show_system_overview.unwrap_or(false) {
+        font.height() + 8
+    } else {
+        0
+    };
+    let start_y = padding + overview_top + overview_h; // leave space for banner, tabs, overview
+    let available_h = h - start_y - padding;
+    let visible = (available_h / (tile_h + padding)).max(1) as usize;
+
+    let mut recent_played = recent::load();
+    let mut played_at = recent::played_map(&recent_played);
+    // "played 2d ago" badge textures by label; there are only a handful of distinct labels
+    let mut badge_textures: HashMap<String, Texture> = HashMap::new();
+    // autolaunch_last: select the most recently played ROM and start a countdown to launch it;
+    // any button press during the countdown cancels it
+    let mut autolaunch: Option<Instant> = None;
+    if config.autolaunch_last.unwrap_or(false) {
+        if let Some(last) = recent_played.first() {
+            let sys_idx = systems_vec.iter().position(|s| s == &last.system);
+            let roms = groups.get(&last.system).cloned().unwrap_or_default();
+            if let (Some(si), Some(ri)) = (sys_idx, roms.iter().position(|r| r == &last.rom)) {
+                current_system_idx = si;
+                current_roms = roms;
+                at_system_list = false;
+                (selected, scroll_offset) =
+                    move_selection(0, 0, current_roms.len(), visible, ri as isize);
+                text_textures.clear();
+                for _ in 0..current_roms.len() {
+                    text_textures.push(None);
+                }
+                autolaunch = Some(Instant::now());
+            }
+        }
+    }
+
+    'running: loop {
+        let style::Palette {
+            bg_color,
+            tile_selected_c,
+            tile_normal_c,
+            text_primary_c,
+            text_secondary_c,
+            banner_bg_c,
+            banner_text_c,
+            emu_text_c,
+            overlay_rgba,
+            menu_bg_c,
+            menu_box_c,
+            menu_selected_c,
+            menu_title_c,
+            menu_text_c,
+            message_overlay_rgba,
+            scrollbar_track_c,
+            scrollbar_thumb_c,
+            tile_border_c,
+        } = palette;
+
+        // scraper progress; a finished scrape triggers a rescan to pick up the new files
+        while let Ok(progress) = scrape_rx.try_recv() {
+            match progress {
+                scrape::ScrapeProgress::Running { done, total, name } => {
+                    let status = format!("Scraping {}/{}: {}", done + 1, total, name);
+                    menu_message = Some((status.clone(), Instant::now()));
+                    scrape_status = Some(status);
+                }
+                scrape::ScrapeProgress::Finished { failed, total } => {
+                    scrape_status = None;
+                    if failed > 0 {
+                        error_overlay = Some((
+                            format!("Scraper failed for {} of {}", failed, total),
+                            Instant::now(),
+                        ));
+                    }
+                    rescan_request = Some(false);
+                }
+            }
+        }
+        // a finished duplicate search opens its report, waiting while another prompt is up
+        let menu_free = matches!(menu_state, MenuState::Open { .. })
+            || (menu_state == MenuState::Closed && !launching);
+        if menu_free {
+            if let Ok(sets) = dupes_rx.try_recv() {
+                dupes_running = false;
+                menu_state = MenuState::Report {
+                    title: format!("Duplicates: {} sets", sets.len()),
+                    lines: dupes::report_lines(&sets, Path::new(&roms_dir)),
+                    scroll: 0,
+                };
+            }
+        }
+        // keep the progress line up during long scraper runs
+        if let (Some(status), None) = (&scrape_status, &menu_message) {
+            menu_message = Some((status.clone(), Instant::now()));
+        }
+
+        // "Reload config" re-reads config from disk; both it and "Rescan ROMs" re-scan roms
+        if let Some(reload) = rescan_request.take() {
+            let prev_system = systems_vec.get(current_system_idx).cloned();
+            if reload {
+                let loaded = load_config(config_path.as_deref());
+                config = loaded.value;
+                missing_emulators = missing_emulator_systems(&config);
+                menu_chord = configured_menu_chord(&config);
+                menu_chord_hold_ms = config.menu_chord_hold_ms.unwrap_or(0);
+                start_opens_menu = menu_chord == [CButton::Start] && menu_chord_hold_ms == 0;
+                exit_chord = parse_chord("exit_chord", config.exit_chord.as_deref().unwrap_or(&[]));
+                key_map = key_translation(&config);
+                key_repeat = KeyRepeat::from_config(&config);
+                set_background_pad_input(config.exit_chord.is_some());
+                if let Some(msg) = loaded.parse_error {
+                    error_overlay = Some((msg, Instant::now()));
+                }
+            }
+            (groups, roms_with_meta, scan_errors) = scan_grouped(Path::new(&roms_dir), &config);
+            if let Some(msg) = scan_error_message(&scan_errors) {
+                error_overlay = Some((msg, Instant::now()));
+            }
+            disc_sets = if config.merge_discs.unwrap_or(false) {
+                merge_disc_sets(&mut groups)
+            } else {
+                HashMap::new()
+            };
+            system_counts = rom_counts(&groups);
+            systems_vec = build_systems_vec(&config, &system_counts);
+
+            // restore current_system_idx by name if possible
+            current_system_idx = prev_system
+                .and_then(|prev| systems_vec.iter().position(|s| s == &prev))
+                .unwrap_or(0);
+
+            // update current roms and textures
+            if at_system_list {
+                current_roms = system_entries(&roms_dir, &systems_vec);
+                (selected, scroll_offset) = move_selection(
+                    0,
+                    0,
+                    current_roms.len(),
+                    visible,
+                    current_system_idx as isize,
+                );
+            } else {
+                let cur = systems_vec.get(current_system_idx).cloned();
+                current_roms = cur
+                    .as_ref()
+                    .and_then(|s| groups.get(s).cloned())
+                    .unwrap_or_default();
+                selected = 0;
+                scroll_offset = 0;
+            }
+            text_textures.clear();
+            for _ in 0..current_roms.len() {
+                text_textures.push(None);
+            }
+
+            menu_message = if reload {
+                Some(("Config reloaded".to_string(), Instant::now()))
+            } else {
+                let total: usize = systems_vec
+                    .iter()
+                    .map(|s| system_counts.get(s).copied().unwrap_or(0))
+                    .sum();
+                Some((
+                    format!(
+                        "Rescan complete: {} ROMs in {} systems",
+                        total,
+                        systems_vec.len()
+                    ),
+                    Instant::now(),
+                ))
+            };
+        }
+
+        // handle spawn completion
+        if let Ok(res) = rx.try_recv() {
+            launching = false;
+            launch_watch = None;
+            if window_hidden {
+                canvas.window_mut().show();
+                window_hidden = false;
+            }
+            // take focus back so input works right away, without clicking the window first.
+            // Raising alone is not always enough under X11
+            canvas.window_mut().raise();
+            // SAFETY: the window pointer stays valid as long as the canvas owns the window
+            unsafe {
+                sdl2::sys::SDL_SetWindowInputFocus(canvas.window().raw());
+            }
+            // time spent in the emulator doesn't count as idle
+            last_input = Instant::now();
+            if let MenuState::Confirm {
+                action: ConfirmAction::KillEmulator,
+                ..
+            } = menu_state
+            {
+                menu_state = MenuState::Closed;
+            }
+            // an emulator that keeps failing right away is blocked for a while (launch_fail_limit)
+            let fail_limit = config.launch_fail_limit.unwrap_or(3);
+            let mut failures = 0;
+            if let Some((rom, started)) = playing.take() {
+                if res.is_err() && started.elapsed().as_secs() < QUICK_FAIL_SECS {
+                    failures = launch_failures.get(&rom).map_or(0, |f| f.0) + 1;
+                    launch_failures.insert(rom, (failures, Instant::now()));
+                } else {
+                    launch_failures.remove(&rom);
+                }
+            }
+            match res {
+                Err(msg) if fail_limit > 0 && failures >= fail_limit => {
+                    menu_state = MenuState::Report {
+                        title: format!("The emulator failed {} times in a row", failures),
+                        lines: vec![
+                            msg,
+                            String::new(),
+                            format!(
+                                "This game can't be started again for {}s.",
+                                launch_cooldown(&config, failures)
+                            ),
+                            "Check the emulator and its settings for this system.".to_string(),
+                        ],
+                        scroll: 0,
+                    };
+                }
+                Err(msg) => error_overlay = Some((msg, Instant::now())),
+                Ok(()) => {}
+            }
+        }
+
+        // handle global kill requests (from X11 hotkey)
+        if kill_rx.try_recv().is_ok() {
+            // with nothing running there is nothing to confirm; the kill reports that instead
+            if config.confirm_kill.unwrap_or(false) && emu::emulator_running(&current_child) {
+                // come to the front so the question is actually seen over the emulator
+                if window_hidden {
+                    canvas.window_mut().show();
+                    window_hidden = false;
+                }
+                canvas.window_mut().raise();
+                menu_state = MenuState::Confirm {
+                    title: "Kill the running emulator?".to_string(),
+                    options: ["Kill emulator", "Resume"],
+                    action: ConfirmAction::KillEmulator,
+                    selected: 1,
+                };
+            } else {
+                menu_message = Some((emu::kill_current_emulator(&current_child), Instant::now()));
+            }
+        }
+
+        // exit_chord: held on any pad while an emulator runs, kills it
+        if !exit_chord.is_empty() {
+            let held = controllers
+                .iter()
+                .any(|c| exit_chord.iter().all(|b| c.button(*b)));
+            if !held {
+                exit_chord_fired = false;
+            } else if !exit_chord_fired && emu::emulator_running(&current_child) {
+                exit_chord_fired = true;
+                menu_message = Some((emu::kill_current_emulator(&current_child), Instant::now()));
+            }
+        }
+
+        // watchdog: an emulator that is still running but never took focus from us is probably
+        // stuck without a window; offer to kill it (0 disables)
+        let launch_timeout = config.launch_timeout_secs.unwrap_or(30);
+        if let Some(started) = launch_watch {
+            if launch_timeout > 0
+                && started.elapsed().as_secs() >= launch_timeout
+                && menu_state == MenuState::Closed
+            {
+                launch_watch = None;
+                if window_hidden {
+                    canvas.window_mut().show();
+                    window_hidden = false;
+                }
+                menu_state = MenuState::Confirm {
+                    title: format!("Emulator has not appeared after {}s", launch_timeout),
+                    options: ["Kill emulator", "Keep waiting"],
+                    action: ConfirmAction::KillEmulator,
+                    selected: 1,
+                };
+            }
+        }
+
+        // collect menu events when menu is open so main UI won't also react
+        let mut menu_events: Vec<sdl2::event::Event> = Vec::new();
+        // ROM to open the detail view for
+        let mut detail_request: Option<PathBuf> = None;
+        // F1 or ? pressed this frame
+        let mut help_request = false;
+        // B or Backspace pressed on the ROM list this frame
+        let mut back_pressed = false;
+        // volume change asked for this frame, in percent
+        let mut volume_step: Option<i8> = None;
+        // ROM the user wants to pick an emulator for this frame
+        let mut emulator_pick_request: Option<PathBuf> = None;
+        // point tapped/clicked this frame (press and release without dragging)
+        let mut tap: Option<(i32, i32)> = None;
+        // used to detect a system switch this frame
+        let frame_start_system_idx = current_system_idx;
+
+        let mut events: Vec<Event> = Vec::new();
+        for event in event_pump.poll_iter() {
+            let event = translate_key(event, &key_map);
+            if !key_repeat.observe(&event) {
+                continue;
+            }
+            // a d-pad that reports as a hat acts as a controller d-pad, one step per press. Pads
+            // opened as controllers already send the d-pad as buttons
+            if let Event::JoyHatMotion {
+                timestamp,
+                which,
+                hat_idx,
+                state,
+            } = event
+            {
+                if !controllers.iter().any(|c| c.instance_id() == which) {
+                    let prev = hat_states
+                        .insert((which, hat_idx), state)
+                        .unwrap_or(HatState::Centered);
+                    events.extend(hat_presses(prev, state).into_iter().map(|button| {
+                        Event::ControllerButtonDown {
+                            timestamp,
+                            which,
+                            button,
+                        }
+                    }));
+                }
+                continue;
+            }
+            events.push(event);
+        }
+        events.extend(key_repeat.due());
+        for event in events {
+            // with exit_chord on, pad input arrives even while the emulator has focus; it was
+            // meant for the game, not for us. Prompts shown over the emulator (confirm_kill, the
+            // launch watchdog) still take it
+            let focused = canvas.window().window_flags()
+                & sdl2::sys::SDL_WindowFlags::SDL_WINDOW_INPUT_FOCUS as u32
+                != 0;
+            if launching
+                && !exit_chord.is_empty()
+                && !focused
+                && menu_state == MenuState::Closed
+                && matches!(
+                    event,
+                    Event::ControllerButtonDown { .. }
+                        | Event::ControllerAxisMotion { .. }
+                        | Event::JoyButtonDown { .. }
+                        | Event::JoyAxisMotion { .. }
+                        | Event::JoyHatMotion { .. }
+                )
+            {
+                continue;
+            }
+            if is_user_input(&event) {
+                last_input = Instant::now();
+                if attract_since.take().is_some() {
+                    // the push that wakes the screen doesn't also navigate once the stick is held
+                    if let Event::JoyAxisMotion {
+                        which,
+                        axis_idx,
+                        value,
+                        ..
+                    } = event
+                    {
+                        axis_dirs.insert((which, axis_idx), axis_direction(value));
+                    }
+                    continue;
+                }
+            }
+            if autolaunch.is_some() {
+                if let Event::KeyDown { .. }
+                | Event::ControllerButtonDown { .. }
+                | Event::JoyButtonDown { .. }
+                | Event::MouseButtonDown { .. }
+                | Event::FingerDown { .. } = event
+                {
+                    autolaunch = None;
+                    menu_message = Some(("Autolaunch cancelled".to_string(), Instant::now()));
+                    continue;
+                }
+            }
+            // losing focus while launching means the emulator window came up
+            if let Event::Window {
+                win_event: WindowEvent::FocusLost,
+                ..
+            } = event
+            {
+                launch_watch = None;
+            }
+            // coming back to the frontend while the emulator still runs (alt-tab, the emulator
+            // minimized itself): offer to kill it
+            if let Event::Window {
+                win_event: WindowEvent::FocusGained,
+                ..
+            } = event
+            {
+                if config.refocus_prompt.unwrap_or(false)
+                    && menu_state == MenuState::Closed
+                    && emu::emulator_running(&current_child)
+                {
+                    menu_state = MenuState::Confirm {
+                        title: "The emulator is still running".to_string(),
+                        options: ["Kill emulator", "Resume"],
+                        action: ConfirmAction::KillEmulator,
+                        selected: 1,
+                    };
+                }
+            }
+            // If a menu or remap overlay is open, buffer events for the menu and skip main UI handling
+            if let MenuState::Open { .. }
+            | MenuState::Remap { .. }
+            | MenuState::DiscSelect { .. }
+            | MenuState::Confirm { .. }
+            | MenuState::EmulatorSelect { .. }
+            | MenuState::Detail { .. }
+            | MenuState::Report { .. } = menu_state
+            {
+                menu_events.push(event);
+                continue;
+            }
+            match event {
+                Event::Quit { .. } => break 'running,
+                // with a marquee window open, closing the main one doesn't end with Quit
+                Event::Window {
+                    window_id,
+                    win_event: WindowEvent::Close,
+                    ..
+                } if window_id == canvas.window().id() => break 'running,
+                // volume_control: +/- keys, or Back held with D-pad up/down on a controller
+                Event::KeyDown {
+                    keycode:
+                        Some(
+                            k @ (Keycode::Equals
+                            | Keycode::Plus
+                            | Keycode::KpPlus
+                            | Keycode::Minus
+                            | Keycode::KpMinus),
+                        ),
+                    ..
+                } if volume_control => {
+                    volume_step = Some(if matches!(k, Keycode::Minus | Keycode::KpMinus) {
+                        -VOLUME_STEP
+                    } else {
+                        VOLUME_STEP
+                    });
+                }
+                Event::ControllerButtonDown {
+                    button: b @ (CButton::DPadUp | CButton::DPadDown),
+                    ..
+                } if volume_control && controllers.iter().any(|c| c.button(CButton::Back)) => {
+                    volume_step = Some(if b == CButton::DPadDown {
+                        -VOLUME_STEP
+                    } else {
+                        VOLUME_STEP
+                    });
+                }
+                // help overlay, also while launching
+                Event::KeyDown {
+                    keycode: Some(Keycode::F1 | Keycode::Question),
+                    ..
+                } => help_request = true,
+                Event::KeyDown {
+                    keycode: Some(Keycode::Slash),
+                    keymod,
+                    ..
+                } if keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD) => help_request = true,
+                // allow opening the menu with 'C' regardless of launching state
+                Event::KeyDown {
+                    keycode: Some(Keycode::C),
+                    ..
+                } => {
+                    menu_state = MenuState::Open {
+                        items: menu_items(&config),
+                        selected: 0,
+                    };
+                    // try to raise the SDL window so menu is visually on top
+                    let _ = canvas.window_mut().raise();
+                    println!("Menu opened (key C)");
+                }
+                // allow opening the menu with the controller Start button even when other guards exist
+                Event::ControllerButtonDown {
+                    button: CButton::Start,
+                    ..
+                } if start_opens_menu => {
+                    menu_state = MenuState::Open {
+                        items: menu_items(&config),
+                        selected: 0,
+                    };
+                    let _ = canvas.window_mut().raise();
+                    println!("Menu opened (controller Start)");
+                }
+                // joystick button events: map Start (common idx 7) to open menu; otherwise handle as joystick buttons
+                Event::JoyButtonDown {
+                    which, button_idx, ..
+                } => {
+                    println!("Joystick button event idx: {}", button_idx);
+                    // typical mapping: Start often appears as button index 7 on some drivers
+                    if button_idx == 7 {
+                        menu_state = MenuState::Open {
+                            items: menu_items(&config),
+                            selected: 0,
+                        };
+                        let _ = canvas.window_mut().raise();
+                        println!("Menu opened (joy idx 7)");
+                        continue;
+                    }
+                    // if not launching, handle joystick button actions (fallback)
+                    if !launching {
+                        match button_idx {
+                            0 => {
+                                // common: A
+                                launch_request = current_roms.get(selected).cloned();
+                            }
+                            // B; pads opened as controllers send it as CButton::B, and their
+                            // raw index 1 can be another face button
+                            1 if !controllers.iter().any(|c| c.instance_id() == which) => {
+                                back_pressed = true
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+                // Escape: close menu if open, otherwise quit
+                Event::KeyDown {
+                    keycode: Some(Keycode::Escape),
+                    ..
+                } => match menu_state {
+                    MenuState::Open { .. } => {
+                        menu_state = MenuState::Closed;
+                    }
+                    _ => break 'running,
+                },
+                Event::KeyDown {
+                    keycode: Some(k), ..
+                } if !launching => {
+                    match k {
+                        Keycode::C => {
+                            // open settings menu (changed to 'C')
+                            menu_state = MenuState::Open {
+                                items: menu_items(&config),
+                                selected: 0,
+                            };
+                            println!("Menu opened (key C alt)");
+                        }
+                        Keycode::Left => {
+                            // switch to previous system
+                            if let Some(i) = prev_system_idx(current_system_idx, systems_vec.len())
+                            {
+                                current_system_idx = i;
+                            }
+                        }
+                        Keycode::Right => {
+                            // switch to next system
+                            if let Some(i) = next_system_idx(current_system_idx, systems_vec.len())
+                            {
+                                current_system_idx = i;
+                            }
+                        }
+                        Keycode::Up => {
+                            if selected > 0 {
+                                selected -= 1;
+                                if selected < scroll_offset {
+                                    scroll_offset = selected;
+                                }
+                            }
+                        }
+                        Keycode::Down => {
+                            if selected + 1 < current_roms.len() {
+                                selected += 1;
+                                if selected >= scroll_offset + visible {
+                                    scroll_offset = selected - visible + 1;
+                                }
+                            }
+                        }
+                        Keycode::PageUp | Keycode::PageDown => {
+                            let delta = if k == Keycode::PageUp {
+                                -(visible as isize)
+                            } else {
+                                visible as isize
+                            };
+                            (selected, scroll_offset) = move_selection(
+                                selected,
+                                scroll_offset,
+                                current_roms.len(),
+                                visible,
+                                delta,
+                            );
+                        }
+                        Keycode::Home | Keycode::End => {
+                            let delta = if k == Keycode::Home {
+                                isize::MIN
+                            } else {
+                                isize::MAX
+                            };
+                            (selected, scroll_offset) = move_selection(
+                                selected,
+                                scroll_offset,
+                                current_roms.len(),
+                                visible,
+                                delta,
+                            );
+                        }
+                        Keycode::W => {
+                            // toggle fullscreen/windowed for debugging
+                            if is_fullscreen {
+                                let _ = canvas.window_mut().set_fullscreen(FullscreenType::Off);
+                                is_fullscreen = false;
+                                println!("Toggled windowed mode");
+                            } else {
+                                let _ = canvas.window_mut().set_fullscreen(fullscreen_type);
+                                is_fullscreen = true;
+                                println!("Toggled fullscreen mode");
+                            }
+                        }
+                        Keycode::Return => {
+                            launch_request = current_roms.get(selected).cloned();
+                        }
+                        Keycode::E => {
+                            emulator_pick_request = current_roms.get(selected).cloned();
+                        }
+                        Keycode::I => {
+                            detail_request = current_roms.get(selected).cloned();
+                        }
+                        Keycode::Backspace => back_pressed = true,
+                        // open the selected ROM's folder in the file manager
+                        Keycode::O => {
+                            if let Some(dir) = current_roms.get(selected).and_then(|r| r.parent()) {
+                                let program = config.file_manager.as_deref().unwrap_or("");
+                                let msg = match emu::open_folder(program, dir) {
+                                    Ok(()) => format!("Opening {}", dir.display()),
+                                    Err(e) => e,
+                                };
+                                menu_message = Some((msg, Instant::now()));
+                            }
+                        }
+                        // 1-9 and 0 jump straight to the 1st-10th system
+                        Keycode::Num1
+                        | Keycode::Num2
+                        | Keycode::Num3
+                        | Keycode::Num4
+                        | Keycode::Num5
+                        | Keycode::Num6
+                        | Keycode::Num7
+                        | Keycode::Num8
+                        | Keycode::Num9
+                        | Keycode::Num0 => {
+                            let i = if k == Keycode::Num0 {
+                                9
+                            } else {
+                                (k as i32 - Keycode::Num1 as i32) as usize
+                            };
+                            if i < systems_vec.len() {
+                                current_system_idx = i;
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                // (Escape to quit is handled above)
+                Event::ControllerButtonDown { button, .. } if !launching => {
+                    println!("Controller button event: {:?}", button);
+                    match button {
+                        CButton::Start if start_opens_menu => {
+                            // open settings menu
+                            menu_state = MenuState::Open {
+                                items: menu_items(&config),
+                                selected: 0,
+                            };
+                            println!("Menu opened (controller Start alt)");
+                        }
+                        CButton::DPadLeft => {
+                            if let Some(i) = prev_system_idx(current_system_idx, systems_vec.len())
+                            {
+                                current_system_idx = i;
+                            }
+                        }
+                        CButton::DPadRight => {
+                            if let Some(i) = next_system_idx(current_system_idx, systems_vec.len())
+                            {
+                                current_system_idx = i;
+                            }
+                        }
+                        CButton::DPadUp => {
+                            if selected > 0 {
+                                selected -= 1;
+                                if selected < scroll_offset {
+                                    scroll_offset = selected;
+                                }
+                            }
+                        }
+                        CButton::DPadDown => {
+                            if selected + 1 < current_roms.len() {
+                                selected += 1;
+                                if selected >= scroll_offset + visible {
+                                    scroll_offset = selected - visible + 1;
+                                }
+                            }
+                        }
+                        CButton::A => {
+                            launch_request = current_roms.get(selected).cloned();
+                        }
+                        CButton::Y => {
+                            emulator_pick_request = current_roms.get(selected).cloned();
+                        }
+                        CButton::X => {
+                            detail_request = current_roms.get(selected).cloned();
+                        }
+                        CButton::LeftShoulder | CButton::RightShoulder => {
+                            // a page at a time; with Back held, to the first/last ROM
+                            let jump = controllers.iter().any(|c| c.button(CButton::Back));
+                            let delta = match (button == CButton::LeftShoulder, jump) {
+                                (true, false) => -(visible as isize),
+                                (false, false) => visible as isize,
+                                (true, true) => isize::MIN,
+                                (false, true) => isize::MAX,
+                            };
+                            (selected, scroll_offset) = move_selection(
+                                selected,
+                                scroll_offset,
+                                current_roms.len(),
+                                visible,
+                                delta,
+                            );
+                        }
+                        CButton::B => back_pressed = true,
+                        _ => {}
+                    }
+                }
+
+                Event::JoyAxisMotion {
+                    which,
+                    axis_idx,
+                    value,
+                    ..
+                } if !launching => {
+                    // system_axes / list_axes, by default 0 = left X and 1 = left Y. An axis acts
+                    // once when pushed past the threshold and again only after coming back
+                    let dir = axis_direction(value);
+                    let prev = axis_dirs.insert((which, axis_idx), dir).unwrap_or(0);
+                    if dir == 0 || dir == prev {
+                        continue;
+                    }
+                    let axes = |list: &Option<Vec<u8>>, default: u8| {
+                        list.as_deref().unwrap_or(&[default]).contains(&axis_idx)
+                    };
+                    if axes(&config.system_axes, 0) {
+                        // left/right switch systems
+                        let step = if dir < 0 {
+                            prev_system_idx(current_system_idx, systems_vec.len())
+                        } else {
+                            next_system_idx(current_system_idx, systems_vec.len())
+                        };
+                        if let Some(i) = step {
+                            current_system_idx = i;
+                        }
+                    } else if axes(&config.list_axes, 1) {
+                        // up/down navigate list
+                        if dir < 0 {
+                            if selected > 0 {
+                                selected -= 1;
+                                if selected < scroll_offset {
+                                    scroll_offset = selected;
+                                }
+                            }
+                        } else if selected + 1 < current_roms.len() {
+                            selected += 1;
+                            if selected >= scroll_offset + visible {
+                                scroll_offset = selected - visible + 1;
+                            }
+                        }
+                    }
+                }
+                // mouse and touch: tap selects a tile, tapping the selected tile launches it,
+                // dragging or the wheel scrolls; taps on the banner switch systems
+                Event::MouseButtonDown {
+                    which,
+                    mouse_btn: MouseButton::Left,
+                    x,
+                    y,
+                    ..
+                } if !launching && which != TOUCH_MOUSE_ID => {
+                    press = Some((x, y));
+                    drag_acc = 0;
+                    dragged = false;
+                }
+                Event::FingerDown { x, y, .. } if !launching => {
+                    press = Some(((x * w as f32) as i32, (y * h as f32) as i32));
+                    drag_acc = 0;
+                    dragged = false;
+                }
+                Event::MouseMotion { which, yrel, .. }
+                    if !launching && which != TOUCH_MOUSE_ID && press.is_some() =>
+                {
+                    drag_acc += yrel;
+                }
+                Event::FingerMotion { dy, .. } if !launching && press.is_some() => {
+                    drag_acc += (dy * h as f32) as i32;
+                }
+                Event::MouseButtonUp {
+                    which,
+                    mouse_btn: MouseButton::Left,
+                    ..
+                } if which != TOUCH_MOUSE_ID => {
+                    if !dragged && !launching {
+                        tap = press;
+                    }
+                    press = None;
+                }
+                Event::FingerUp { .. } => {
+                    if !dragged && !launching {
+                        tap = press;
+                    }
+                    press = None;
+                }
+                Event::MouseWheel { y, .. } if !launching => {
+                    (selected, scroll_offset) = move_selection(
+                        selected,
+                        scroll_offset,
+                        current_roms.len(),
+                        visible,
+                        -(y as isize),
+                    );
+                }
+                // Menu input handling (when menu is open)
+                // Note: we keep it simple and handle key/controller events in the main loop below when rendering the menu
+                _ => {}
+            }
+        }
+
+        // commands from the control socket act like the matching input
+        #[cfg(unix)]
+        while let Ok(req) = control_rx.try_recv() {
+            let reply = match req.cmd {
+                control::ControlCommand::List => {
+                    let mut out = format!(
+                        "system\t{}",
+                        systems_vec
+                            .get(current_system_idx)
+                            .map(|s| s.as_str())
+                            .unwrap_or("")
+                    );
+                    for (i, rom) in current_roms.iter().enumerate() {
+                        let name = rom.file_name().and_then(|s| s.to_str()).unwrap_or("");
+                        out.push_str(&format!("\n{}\t{}", i, name));
+                    }
+                    out.push_str("\nend");
+                    out
+                }
+                control::ControlCommand::Select(i) if i < current_roms.len() => {
+                    (selected, scroll_offset) = move_selection(
+                        selected,
+                        scroll_offset,
+                        current_roms.len(),
+                        visible,
+                        i as isize - selected as isize,
+                    );
+                    "ok".to_string()
+                }
+                control::ControlCommand::Select(_) => "error: index out of range".to_string(),
+                control::ControlCommand::Launch if launching => {
+                    "error: an emulator is already running".to_string()
+                }
+                control::ControlCommand::Launch => match current_roms.get(selected) {
+                    Some(rom) => {
+                        launch_request = Some(rom.clone());
+                        "ok".to_string()
+                    }
+                    None => "error: nothing selected".to_string(),
+                },
+                control::ControlCommand::Kill => emu::kill_current_emulator(&current_child),
+                control::ControlCommand::NextSystem => {
+                    if let Some(i) = next_system_idx(current_system_idx, systems_vec.len()) {
+                        current_system_idx = i;
+                    }
+                    "ok".to_string()
+                }
+            };
+            let _ = req.reply.send(reply);
+        }
+
+        // dragging moves the list one row per tile height dragged; dragging up moves down
+        let row_h = tile_h + padding;
+        if press.is_some() && drag_acc.abs() >= row_h {
+            let rows = drag_acc / row_h;
+            drag_acc -= rows * row_h;
+            dragged = true;
+            (selected, scroll_offset) = move_selection(
+                selected,
+                scroll_offset,
+                current_roms.len(),
+                visible,
+                -(rows as isize),
+            );
+        }
+        if let Some((px, py)) = tap {
+            if py < tabs_top {
+                // banner: left half goes to the previous system, right half to the next
+                let step = if px < w / 2 {
+                    prev_system_idx(current_system_idx, systems_vec.len())
+                } else {
+                    next_system_idx(current_system_idx, systems_vec.len())
+                };
+                if let Some(i) = step {
+                    current_system_idx = i;
+                }
+            } else if py < tabs_top + tabs_h {
+                // tab bar: switch to the tapped system
+                let mut edge = -tabs_scroll;
+                for (i, s) in systems_vec.iter().enumerate() {
+                    let label = system_display_name(&config, s);
+                    edge += font.size_of(&label).map(|(w, _)| w as i32).unwrap_or(0) + TAB_PAD * 2;
+                    if px < edge {
+                        current_system_idx = i;
+                        break;
+                    }
+                }
+            } else if px >= start_x && px < start_x + tile_w {
+                match tile_at(
+                    py,
+                    start_y,
+                    tile_h,
+                    padding,
+                    scroll_offset,
+                    current_roms.len(),
+                ) {
+                    Some(i) if i == selected => launch_request = current_roms.get(i).cloned(),
+                    Some(i) => selected = i,
+                    None => {}
+                }
+            }
+        }
+
+        // a handler above switched systems: load its roms and reset selection. On the two_level
+        // system list the selection and the current system move together instead
+        if at_system_list {
+            if current_system_idx != frame_start_system_idx {
+                let delta = current_system_idx as isize - selected as isize;
+                (selected, scroll_offset) =
+                    move_selection(selected, scroll_offset, current_roms.len(), visible, delta);
+            } else if selected < systems_vec.len() {
+                current_system_idx = selected;
+            }
+        } else if current_system_idx != frame_start_system_idx {
+            let cur = systems_vec.get(current_system_idx).cloned();
+            current_roms = cur
+                .as_ref()
+                .and_then(|s| groups.get(s).cloned())
+                .unwrap_or_default();
+            selected = 0;
+            scroll_offset = 0;
+            text_textures.clear();
+            for _ in 0..current_roms.len() {
+                text_textures.push(None);
+            }
+        }
+
+        // menu_chord: open the menu once all its buttons are held long enough; releasing any of
+        // them re-arms it
+        if !start_opens_menu {
+            // not while playing: with exit_chord on the pads are read in the background too
+            let held = !launching
+                && controllers
+                    .iter()
+                    .any(|c| menu_chord.iter().all(|b| c.button(*b)));
+            if !held {
+                menu_chord_held = None;
+            } else if let Some((since, fired)) = menu_chord_held.as_mut() {
+                if !*fired
+                    && since.elapsed().as_millis() as u64 >= menu_chord_hold_ms
+                    && menu_state == MenuState::Closed
+                {
+                    *fired = true;
+                    menu_state = MenuState::Open {
+                        items: menu_items(&config),
+                        selected: 0,
+                    };
+                    canvas.window_mut().raise();
+                    println!("Menu opened (controller chord)");
+                }
+            } else {
+                menu_chord_held = Some((Instant::now(), false));
+            }
+        }
+
+        if config.rumble.unwrap_or(false) && current_system_idx != frame_start_system_idx {
+            rumble_all(&mut controllers, 0x2000, 40);
+        }
+
+        if let Some(started) = autolaunch {
+            if started.elapsed().as_secs() >= AUTOLAUNCH_SECS {
+                autolaunch = None;
+                launch_request = current_roms.get(selected).cloned();
+            }
+        }
+
+        // two_level: A on the system list enters the system (ROM actions don't apply there);
+        // B on a ROM list goes back up, unless it is dismissing a message
+        if at_system_list {
+            detail_request = None;
+            emulator_pick_request = None;
+            if launch_request.take().is_some() && !systems_vec.is_empty() {
+                at_system_list = false;
+                current_roms = systems_vec
+                    .get(current_system_idx)
+                    .and_then(|s| groups.get(s).cloned())
+                    .unwrap_or_default();
+                selected = 0;
+                scroll_offset = 0;
+                text_textures.clear();
+                for _ in 0..current_roms.len() {
+                    text_textures.push(None);
+                }
+            }
+        } else if two_level && back_pressed && error_overlay.is_none() && menu_message.is_none() {
+            back_pressed = false;
+            at_system_list = true;
+            current_roms = system_entries(&roms_dir, &systems_vec);
+            (selected, scroll_offset) = move_selection(
+                0,
+                0,
+                current_roms.len(),
+                visible,
+                current_system_idx as isize,
+            );
+            text_textures.clear();
+            for _ in 0..current_roms.len() {
+                text_textures.push(None);
+            }
+        }
+
+        // resolve a requested launch
+        if let Some(rom_path) = launch_request.take() {
+            if let Some(s) = systems_vec.get(current_system_idx).cloned() {
+                match resolve_launch_template(&config, &s, &rom_path) {
+                    Ok(t) if config.confirm_launch.unwrap_or(false) => {
+                        menu_state = launch_confirm(t, rom_path);
+                    }
+                    Ok(t) => pending_launch = Some((t, rom_path)),
+                    Err(msg) => error_overlay = Some((msg, Instant::now())),
+                }
+            }
+        }
+
+        // offer the system's emulator and its alternates; launch directly when there are none
+        if let Some(rom_path) = emulator_pick_request.take() {
+            if let Some(s) = systems_vec.get(current_system_idx).cloned() {
+                let tmpl = config.systems.as_ref().and_then(|m| m.get(&s));
+                match tmpl.and_then(|t| t.alt_programs.as_ref()) {
+                    Some(alts) if !alts.is_empty() => {
+                        let mut choices = vec![tmpl.unwrap().clone()];
+                        choices.extend(alts.iter().cloned());
+                        menu_state = MenuState::EmulatorSelect {
+                            rom: rom_path,
+                            choices,
+                            selected: 0,
+                        };
+                    }
+                    _ => match resolve_launch_template(&config, &s, &rom_path) {
+                        Ok(t) if config.confirm_launch.unwrap_or(false) => {
+                            menu_state = launch_confirm(t, rom_path);
+                        }
+                        Ok(t) => pending_launch = Some((t, rom_path)),
+                        Err(msg) => error_overlay = Some((msg, Instant::now())),
+                    },
+                }
+            }
+        }
+
+        if let Some(delta) = volume_step.take() {
+            match volume::change(&volume_mixer, delta) {
+                Ok(pct) => volume_osd = Some((pct, Instant::now())),
+                Err(e) => menu_message = Some((format!("Volume: {}", e), Instant::now())),
+            }
+        }
+
+        if back_pressed {
+            if error_overlay.is_some() || menu_message.is_some() {
+                error_overlay = None;
+                menu_message = None;
+            } else {
+                match back_action {
+                    BackAction::Nothing => {}
+                    BackAction::FirstRom => {
+                        (selected, scroll_offset) = move_selection(
+                            selected,
+                            scroll_offset,
+                            current_roms.len(),
+                            visible,
+                            isize::MIN,
+                        );
+                    }
+                    BackAction::Menu => {
+                        menu_state = MenuState::Open {
+                            items: menu_items(&config),
+                            selected: 0,
+                        };
+                    }
+                    BackAction::Quit => break 'running,
+                }
+            }
+        }
+
+        if help_request {
+            menu_state = MenuState::Report {
+                title: "Help".to_string(),
+                lines: help_lines(&config, config_path.as_deref(), &roms_dir, &font_path),
+                scroll: 0,
+            };
+        }
+
+        // detail view: metadata (read now, only for ROMs the scan found a file for) and file facts
+        if let Some(rom) = detail_request.take() {
+            let meta = if roms_with_meta.contains(&rom) {
+                meta::load(&rom).unwrap_or_default()
+            } else {
+                meta::RomMeta::default()
+            };
+            let title = meta.title.clone().unwrap_or_else(|| {
+                rom.file_stem()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string()
+            });
+            let mut lines: Vec<String> = Vec::new();
+            if let Some(genre) = &meta.genre {
+                lines.push(format!("Genre: {}", genre));
+            }
+            if let Some(players) = meta.players {
+                lines.push(format!("Players: {}", players));
+            }
+            if let Some(desc) = &meta.description {
+                let width_of = |s: &str| -> u32 { font.size_of(s).map(|(w, _)| w).unwrap_or(0) };
+                lines.extend(text::wrap_to_lines(
+                    desc,
+                    (w * 2 / 3 - 32) as u32,
+                    8,
+                    width_of,
+                ));
+            }
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push(format!(
+                "File: {}",
+                rom.file_name().unwrap_or_default().to_string_lossy()
+            ));
+            if let Ok(md) = std::fs::metadata(&rom) {
+                lines.push(format!("Size: {}", format_size(md.len())));
+            }
+            if let Some(dir) = rom.parent() {
+                lines.push(format!("Folder: {}", dir.display()));
+            }
+            menu_state = MenuState::Detail { rom, title, lines };
+        }
+
+        // start (or ask which disc to start for) a resolved launch
+        if let Some((t, rom_path)) = pending_launch.take() {
+            match disc_sets.get(&rom_path) {
+                Some(discs) if discs.len() > 1 => {
+                    menu_state = MenuState::DiscSelect {
+                        template: Box::new(t),
+                        discs: discs.clone(),
+                        selected: 0,
+                    };
+                }
+                _ => ready_launch = Some((t, rom_path)),
+            }
+        }
+
+        // start the emulator, or with dry_run just show the command it would run
+        if let Some((t, rom_path)) = ready_launch.take() {
+            let fail_limit = config.launch_fail_limit.unwrap_or(3);
+            let blocked_for = launch_failures
+                .get(&rom_path)
+                .filter(|(n, _)| fail_limit > 0 && *n >= fail_limit)
+                .map(|(n, last)| {
+                    launch_cooldown(&config, *n).saturating_sub(last.elapsed().as_secs())
+                })
+                .filter(|secs| *secs > 0);
+            if let Some(secs) = blocked_for {
+                error_overlay = Some((
+                    format!("This game keeps failing to start; try again in {}s", secs),
+                    Instant::now(),
+                ));
+            } else if config.dry_run.unwrap_or(false) {
+                let line = emu::command_line(&t, &rom_path);
+                println!("Dry run: {}", line);
+                menu_message = Some((line, Instant::now()));
+            } else {
+                // capture_output: emulator output goes to logs/<rom name>.log in the data dir
+                let log_path = if config.capture_output.unwrap_or(false) {
+                    rom_path.file_stem().and_then(|stem| {
+                        paths::user_data_path(&format!("logs/{}.log", stem.to_string_lossy()))
+                    })
+                } else {
+                    None
+                };
+                launching = true;
+                launch_watch = Some(Instant::now());
+                playing = Some((rom_path.clone(), Instant::now()));
+                if let Some(s) = systems_vec.get(current_system_idx) {
+                    recent::record(&mut recent_played, s, &rom_path);
+                    played_at = recent::played_map(&recent_played);
+                }
+                if config.rumble.unwrap_or(false) {
+                    rumble_all(&mut controllers, 0xC000, 250);
+                }
+                if let Some(notice) = &t.post_launch_notice {
+                    menu_message = Some((notice.clone(), Instant::now()));
+                }
+                emu::spawn_in_background(
+                    t,
+                    rom_path,
+                    current_child.clone(),
+                    status_file_for(&config, &systems_vec, current_system_idx),
+                    log_path,
+                    tx.clone(),
+                );
+                // some window managers keep a fullscreen window above the emulator's
+                if config.hide_on_launch.unwrap_or(false) {
+                    canvas.window_mut().hide();
+                    window_hidden = true;
+                }
+            }
+        }
+
+        // render
+        canvas.set_draw_color(bg_color);
+        canvas.clear();
+
+        // ensure scroll offset valid
+        if scroll_offset >= current_roms.len() && !current_roms.is_empty() {
+            scroll_offset = current_roms.len() - 1;
+        }
+
+        // dim the list when the current system's emulator is not installed (on the two_level
+        // system list, each system whose emulator is missing)
+        let current_missing = systems_vec
+            .get(current_system_idx)
+            .map(|s| missing_emulators.contains(s))
+            .unwrap_or(false);
+        let dim = |c: Color| -> Color { Color::RGB(c.r / 2, c.g / 2, c.b / 2) };
+
+        for (idx, rom) in current_roms
+            .iter()
+            .enumerate()
+            .skip(scroll_offset)
+            .take(visible)
+        {
+            let i = idx;
+            let emulator_missing = if at_system_list {
+                systems_vec
+                    .get(i)
+                    .is_some_and(|s| missing_emulators.contains(s))
+            } else {
+                current_missing
+            };
+            let tile_text_c = if emulator_missing {
+                text_secondary_c
+            } else {
+                text_primary_c
+            };
+            let x = start_x;
+            let y = start_y + ((i - scroll_offset) as i32) * (tile_h + padding);
+            let rect = Rect::new(x, y, tile_w as u32, tile_h as u32);
+
+            let tile_c = if i == selected {
+                match selection_pulse {
+                    // 0..1..0 once per pulse, dimming the highlight by up to 40% toward the
+                    // normal tile color
+                    Some(speed) => {
+                        let phase = pulse_start.elapsed().as_secs_f32() * speed;
+                        let t = 0.5 - 0.5 * (phase * std::f32::consts::TAU).cos();
+                        style::blend(tile_selected_c, tile_normal_c, t * 0.4)
+                    }
+                    None => tile_selected_c,
+                }
+            } else {
+                tile_normal_c
+            };
+            let fill_rect = if tile_border_width > 0 {
+                canvas.set_draw_color(tile_border_c);
+                fill_rounded_rect(&mut canvas, rect, tile_corner_radius);
+                let bw = tile_border_width;
+                Rect::new(
+                    x + bw,
+                    y + bw,
+                    (tile_w - bw * 2).max(1) as u32,
+                    (tile_h - bw * 2).max(1) as u32,
+                )
+            } else {
+                rect
+            };
+            canvas.set_draw_color(if emulator_missing {
+                dim(tile_c)
+            } else {
+                tile_c
+            });
+            fill_rounded_rect(
+                &mut canvas,
+                fill_rect,
+                tile_corner_radius - tile_border_width,
+            );
+            if i == selected {
+                // outline the selection (style selection_border, thicker in accessibility mode)
+                canvas.set_draw_color(text_primary_c);
+                for inset in 0..selection_border {
+                    let _ = canvas.draw_rect(Rect::new(
+                        x + inset,
+                        y + inset,
+                        (tile_w - inset * 2).max(0) as u32,
+                        (tile_h - inset * 2).max(0) as u32,
+                    ));
+                }
+            }
+
+            // filename text rendering (lazy create texture)
+            if text_textures.get(i).and_then(|t| t.as_ref()).is_none() {
+                let label = if at_system_list {
+                    systems_vec.get(i).map(|s| {
+                        let count = system_counts.get(s).copied().unwrap_or(0);
+                        format!("{} ({})", system_display_name(&config, s), count)
+                    })
+                } else {
+                    rom_label(&config, Path::new(&roms_dir), rom)
+                };
+                if let Some(name) = label {
+                    // Wrap the filename into up to max_title_lines lines, truncating the last one
+                    // with an ellipsis if the name still doesn't fit.
+                    let padding = 8; // px padding inside tile
+                    let max_w = (tile_w as u32).saturating_sub((padding * 2) as u32);
+                    let width_of =
+                        |s: &str| -> u32 { font.size_of(s).map(|(w, _)| w).unwrap_or(0) };
+                    let mut line_texts: Vec<Texture> = Vec::new();
+                    for line in text::wrap_to_lines(&name, max_w, max_title_lines, width_of) {
+                        if let Ok(surf) = font.render(&line).blended(tile_text_c) {
+                            if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
+                                line_texts.push(tex);
+                            }
+                        }
+                    }
+                    if let Some(slot) = text_textures.get_mut(i) {
+                        *slot = Some(line_texts);
+                    }
+                }
+            }
+
+            if let Some(Some(text_vec)) = text_textures.get(i) {
+                // draw one or two lines centered vertically in the tile
+                let mut total_h = 0i32;
+                let mut queries: Vec<sdl2::render::TextureQuery> = Vec::new();
+                for tex in text_vec.iter() {
+                    let q = tex.query();
+                    total_h += q.height as i32;
+                    queries.push(q);
+                }
+                // spacing between lines
+                let spacing = 2;
+                total_h += spacing * ((queries.len() as i32) - 1).max(0);
+                let mut cursor_y = y + (tile_h - total_h) / 2; // center vertically
+                for (idx, tex) in text_vec.iter().enumerate() {
+                    let q = &queries[idx];
+                    let tex_w = q.width as i32;
+                    let tex_h = q.height as i32;
+                    let dst_x = x + (tile_w - tex_w) / 2;
+                    let dst_y = cursor_y;
+                    let _ = canvas.copy(
+                        tex,
+                        None,
+                        Rect::new(dst_x, dst_y, tex_w as u32, tex_h as u32),
+                    );
+                    cursor_y += tex_h + spacing;
+                }
+            }
+
+            // last-played badge in the top-right corner
+            if config.show_played_badge.unwrap_or(false) {
+                if let Some(&when) = played_at.get(rom) {
+                    let label = recent::played_ago_label(when);
+                    if !badge_textures.contains_key(&label) {
+                        if let Ok(surf) = font.render(&label).blended(text_secondary_c) {
+                            if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
+                                badge_textures.insert(label.clone(), tex);
+                            }
+                        }
+                    }
+                    if let Some(tex) = badge_textures.get(&label) {
+                        let q = tex.query();
+                        let _ = canvas.copy(
+                            tex,
+                            None,
+                            Rect::new(x + tile_w - q.width as i32 - 6, y + 4, q.width, q.height),
+                        );
+                    }
+                }
+            }
+        }
+
+        // bound the tile text cache on huge lists: drop the rows farthest off screen, they are
+        // re-rendered if scrolled back into view (texture_cache_size 0 = unlimited)
+        let cache_cap = config.texture_cache_size.unwrap_or(256);
+        if cache_cap > 0 {
+            let cached: Vec<usize> = text_textures
+                .iter()
+                .enumerate()
+                .filter(|(_, t)| t.is_some())
+                .map(|(i, _)| i)
+                .collect();
+            for row in rows_to_evict(&cached, scroll_offset, visible, cache_cap) {
+                text_textures[row] = None;
+            }
+        }
+
+        // first-run / empty library help, centered in the list area
+        if systems_vec.is_empty() {
+            let mut help = vec![
+                ("No ROMs found.".to_string(), text_primary_c),
+                (
+                    format!("Put files in {}/<system>/", roms_dir.trim_end_matches('/')),
+                    text_secondary_c,
+                ),
+                (
+                    format!(
+                        "and add the system to the config at {}",
+                        config_path_display
+                    ),
+                    text_secondary_c,
+                ),
+                (
+                    "Press C or Start to open the menu (Reload config after editing).".to_string(),
+                    text_secondary_c,
+                ),
+            ];
+            // the library may be empty because a folder could not be read
+            if let Some(msg) = scan_error_message(&scan_errors) {
+                help.insert(1, (msg, text_primary_c));
+            }
+            let max_w = (w - 40).max(1) as u32;
+            let width_of = |s: &str| -> u32 { font.size_of(s).map(|(w, _)| w).unwrap_or(0) };
+            let mut textures: Vec<Texture> = Vec::new();
+            for (line, color) in help.iter() {
+                for part in text::wrap_to_lines(line, max_w, 3, width_of) {
+                    if let Ok(surf) = font.render(&part).blended(*color) {
+                        if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
+                            textures.push(tex);
+                        }
+                    }
+                }
+            }
+            let line_h = font.height() + 6;
+            let mut cursor_y = start_y + (available_h - line_h * textures.len() as i32) / 2;
+            for tex in textures.iter() {
+                let q = tex.query();
+                let dst_x = (w - q.width as i32) / 2;
+                let _ = canvas.copy(tex, None, Rect::new(dst_x, cursor_y, q.width, q.height));
+                cursor_y += line_h;
+            }
+        }
+
+        // scrollbar in the right-hand padding, only when the list doesn't fit on one screen
+        if let Some((thumb_y, thumb_h)) =
+            scrollbar_thumb(available_h, current_roms.len(), visible, scroll_offset)
+        {
+            let bar_x = (start_x + tile_w + padding - padding / 2 - 3).max(0);
+            canvas.set_draw_color(scrollbar_track_c);
+            let _ = canvas.fill_rect(Rect::new(bar_x, start_y, 3, available_h.max(1) as u32));
+            canvas.set_draw_color(scrollbar_thumb_c);
+            let _ = canvas.fill_rect(Rect::new(bar_x, start_y + thumb_y, 3, thumb_h as u32));
+        }
+
+        // system tabs: every system's label, the current one highlighted, scrolled sideways so
+        // the current tab stays on screen
+        if tabs_h > 0 && !systems_vec.is_empty() {
+            canvas.set_draw_color(banner_bg_c);
+            let _ = canvas.fill_rect(Rect::new(0, tabs_top, w as u32, tabs_h as u32));
+            let labels: Vec<String> = systems_vec
+                .iter()
+                .map(|s| system_display_name(&config, s))
+                .collect();
+            let widths: Vec<i32> = labels
+                .iter()
+                .map(|l| font.size_of(l).map(|(w, _)| w as i32).unwrap_or(0) + TAB_PAD * 2)
+                .collect();
+            tabs_scroll = tab_scroll(&widths, current_system_idx, w, tabs_scroll);
+            let mut tab_x = -tabs_scroll;
+            for (i, label) in labels.iter().enumerate() {
+                let tab_w = widths[i];
+                if tab_x + tab_w > 0 && tab_x < w {
+                    if i == current_system_idx {
+                        canvas.set_draw_color(tile_selected_c);
+                        let _ = canvas.fill_rect(Rect::new(
+                            tab_x,
+                            tabs_top,
+                            tab_w as u32,
+                            tabs_h as u32,
+                        ));
+                    }
+                    if let Ok(surf) = font.render(label).blended(banner_text_c) {
+                        if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
+                            let q = tex.query();
+                            let dst_y = tabs_top + (tabs_h - q.height as i32) / 2;
+                            let _ = canvas.copy(
+                                &tex,
+                                None,
+                                Rect::new(tab_x + TAB_PAD, dst_y, q.width, q.height),
+                            );
+                        }
+                    }
+                }
+                tab_x += tab_w;
+            }
+        }
+
+        // system overview: "NAME (count)" for each system, the current one highlighted. When
+        // they don't all fit, the ones around the current system are shown with "..." markers.
+        if overview_h > 0 && !systems_vec.is_empty() {
+            const GAP: i32 = 16;
+            canvas.set_draw_color(banner_bg_c);
+            let _ = canvas.fill_rect(Rect::new(0, overview_top, w as u32, overview_h as u32));
+            let items: Vec<String> = systems_vec
+                .iter()
+                .map(|s| {
+                    format!(
+                        "{} ({})",
+                        system_display_name(&config, s),
+                        system_counts.get(s).copied().unwrap_or(0)
+                    )
+                })
+                .collect();
+            let widths: Vec<i32> = items
+                .iter()
+                .map(|l| font.size_of(l).map(|(w, _)| w as i32).unwrap_or(0))
+                .collect();
+            let more_w = font.size_of("...").map(|(w, _)| w as i32).unwrap_or(0);
+            let max_w = w - 24 - 2 * (more_w + GAP);
+            let (lo, hi) = overview_window(&widths, current_system_idx, GAP, max_w);
+            let mut parts: Vec<(String, Color)> = Vec::new();
+            if lo > 0 {
+                parts.push(("...".to_string(), text_secondary_c));
+            }
+            for (i, item) in items.iter().enumerate().take(hi).skip(lo) {
+                let c = if i == current_system_idx {
+                    tile_selected_c
+                } else {
+                    text_secondary_c
+                };
+                parts.push((item.clone(), c));
+            }
+            if hi < items.len() {
+                parts.push(("...".to_string(), text_secondary_c));
+            }
+            let mut cursor_x = 12;
+            for (text, c) in parts.iter() {
+                if let Ok(surf) = font.render(text).blended(*c) {
+                    if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
+                        let q = tex.query();
+                        let dst_y = overview_top + (overview_h - q.height as i32) / 2;
+                        let _ =
+                            canvas.copy(&tex, None, Rect::new(cursor_x, dst_y, q.width, q.height));
+                        cursor_x += q.width as i32 + GAP;
+                    }
+                }
+            }
+        }
+
+        // banner
+        canvas.set_draw_color(banner_bg_c);
+        let _ = canvas.fill_rect(Rect::new(0, 0, w as u32, banner_h as u32));
+
+        // render banner text: current system and selected filename + mapped emulator
+        let current_system_name = systems_vec
+            .get(current_system_idx)
+            .cloned()
+            .unwrap_or_else(|| "".to_string());
+        // show system name + count; on the system list `current_roms` holds the systems, so the
+        // highlighted system's ROM count comes from system_counts as on its tile
+        let count = if at_system_list {
+            system_counts
+                .get(&current_system_name)
+                .copied()
+                .unwrap_or(0)
+        } else {
+            current_roms.len()
+        };
+        // the system name is middle-elided so the label takes at most a third of the banner
+        let count_txt = format!(" ({})", count);
+        let width_of = |s: &str| -> u32 { font.size_of(s).map(|(w, _)| w).unwrap_or(0) };
+        let system_label = format!(
+            "{}{}",
+            text::elide_middle_to_width(
+                &system_display_name(&config, &current_system_name),
+                ((w / 3) as u32).saturating_sub(width_of(&count_txt)),
+                width_of,
+            ),
+            count_txt
+        );
+        // left edge of the system label, which the filename must stay clear of
+        let mut system_left = w;
+        if let Some(tex_sys) =
+            banner_system_text.get(&font, &texture_creator, &system_label, banner_text_c)
+        {
+            let q = tex_sys.query();
+            // position system label at the right side of banner to avoid overlapping centered filename
+            let dst_x = (w as i32) - (q.width as i32) - 12;
+            let dst_y = (banner_h - q.height as i32) / 2;
+            let _ = canvas.copy(tex_sys, None, Rect::new(dst_x, dst_y, q.width, q.height));
+            system_left = dst_x;
+        }
+
+        if let Some(rom_path) = current_roms.get(selected).filter(|_| !at_system_list) {
+            if let Some(name) = rom_label(&config, Path::new(&roms_dir), rom_path) {
+                // emulator mapping name
+                let emu_name = config
+                    .systems
+                    .as_ref()
+                    .and_then(|m| m.get(&current_system_name))
+                    .map(|t| t.program.clone())
+                    .or_else(|| config.default.as_ref().map(|d| d.program.clone()));
+
+                // emulator label on the left; a left-aligned filename follows it
+                let mut emu_right = 0;
+                if let Some(emu) = emu_name {
+                    let emu_txt = format!("emu: {}", emu);
+                    if let Some(tex2) =
+                        banner_emu_text.get(&font, &texture_creator, &emu_txt, emu_text_c)
+                    {
+                        let q2 = tex2.query();
+                        let dst_x2 = 12;
+                        let dst_y2 = (banner_h - q2.height as i32) / 2;
+                        let _ =
+                            canvas.copy(tex2, None, Rect::new(dst_x2, dst_y2, q2.width, q2.height));
+                        emu_right = dst_x2 + q2.width as i32;
+                    }
+                }
+
+                // the filename gets the space between the emulator and system labels (centered
+                // text needs it on both sides) and is middle-elided to fit
+                let banner_padding = 12;
+                let name_left = emu_right + banner_padding * 2;
+                let name_right = system_left - banner_padding * 2;
+                let avail = if filename_left {
+                    name_right - name_left
+                } else {
+                    2 * (w / 2 - name_left).min(name_right - w / 2)
+                };
+                let width_of = |s: &str| -> u32 { font.size_of(s).map(|(w, _)| w).unwrap_or(0) };
+                let display_name =
+                    text::elide_middle_to_width(&name, avail.max(0) as u32, width_of);
+
+                if let Some(tex) =
+                    banner_rom_text.get(&font, &texture_creator, &display_name, banner_text_c)
+                {
+                    let q = tex.query();
+                    let dst_x = if filename_left {
+                        name_left
+                    } else {
+                        ((w as i32) - q.width as i32) / 2
+                    };
+                    let dst_y = (banner_h - q.height as i32) / 2;
+                    let _ = canvas.copy(tex, None, Rect::new(dst_x, dst_y, q.width, q.height));
+                }
+            }
+        }
+
+        // attract mode: only once idle on the plain list (not while launching or in a menu)
+        if let Some(mode) = &attract_mode {
+            if attract_since.is_none()
+                && attract_timeout > 0
+                && last_input.elapsed().as_secs() >= attract_timeout
+                && !launching
+                && autolaunch.is_none()
+                && menu_state == MenuState::Closed
+            {
+                attract_since = Some(Instant::now());
+            }
+            if let Some(since) = attract_since {
+                if mode == "dim" {
+                    canvas.set_blend_mode(sdl2::render::BlendMode::Blend);
+                    canvas.set_draw_color(Color::RGBA(0, 0, 0, 200));
+                    let _ = canvas.fill_rect(Rect::new(0, 0, w as u32, h as u32));
+                    canvas.set_blend_mode(sdl2::render::BlendMode::None);
+                } else {
+                    canvas.set_draw_color(Color::RGB(0, 0, 0));
+                    let _ = canvas.fill_rect(Rect::new(0, 0, w as u32, h as u32));
+                    let total: usize = systems_vec
+                        .iter()
+                        .map(|s| groups.get(s).map(Vec::len).unwrap_or(0))
+                        .sum();
+                    if total > 0 {
+                        use std::hash::BuildHasher;
+                        let slide = since.elapsed().as_secs() / ATTRACT_SLIDE_SECS;
+                        let mut idx = (slide_hasher.hash_one(slide) % total as u64) as usize;
+                        let mut pick: Option<(&String, &PathBuf)> = None;
+                        for s in &systems_vec {
+                            let roms = groups.get(s).map(Vec::as_slice).unwrap_or_default();
+                            if idx < roms.len() {
+                                pick = Some((s, &roms[idx]));
+                                break;
+                            }
+                            idx -= roms.len();
+                        }
+                        if let Some((sys, rom)) = pick {
+                            let name = rom.file_stem().unwrap_or_default().to_string_lossy();
+                            let label = system_display_name(&config, sys);
+                            let line_h = font.height();
+                            if let Some(tex) = attract_name_text.get(
+                                &font,
+                                &texture_creator,
+                                &name,
+                                text_primary_c,
+                            ) {
+                                let q = tex.query();
+                                let dst = Rect::new(
+                                    (w - q.width as i32) / 2,
+                                    h / 2 - line_h,
+                                    q.width,
+                                    q.height,
+                                );
+                                let _ = canvas.copy(tex, None, dst);
+                            }
+                            if let Some(tex) = attract_system_text.get(
+                                &font,
+                                &texture_creator,
+                                &label,
+                                text_secondary_c,
+                            ) {
+                                let q = tex.query();
+                                let dst = Rect::new(
+                                    (w - q.width as i32) / 2,
+                                    h / 2 + 8,
+                                    q.width,
+                                    q.height,
+                                );
+                                let _ = canvas.copy(tex, None, dst);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // launching overlay
+        if launching {
+            canvas.set_draw_color(overlay_rgba);
+            let _ = canvas.fill_rect(Rect::new(0, 0, w as u32, h as u32));
+        }
+
+        // autolaunch countdown
+        if let Some(started) = autolaunch {
+            canvas.set_draw_color(overlay_rgba);
+            let _ = canvas.fill_rect(Rect::new(0, 0, w as u32, h as u32));
+            let name = current_roms
+                .get(selected)
+                .and_then(|p| p.file_name())
+                .and_then(|s| s.to_str())
+                .unwrap_or("");
+            let left = AUTOLAUNCH_SECS.saturating_sub(started.elapsed().as_secs());
+            let lines = [
+                format!("Launching {} in {}...", name, left),
+                "Press any button to cancel".to_string(),
+            ];
+            for (i, line) in lines.iter().enumerate() {
+                if let Ok(surf) = font.render(line).blended(text_primary_c) {
+                    if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
+                        let q = tex.query();
+                        let dst_x = (w - q.width as i32) / 2;
+                        let dst_y = h / 2 - font.height() + i as i32 * (font.height() + 8);
+                        let _ = canvas.copy(&tex, None, Rect::new(dst_x, dst_y, q.width, q.height));
+                    }
+                }
+            }
+        }
+
+        // error overlay for missing mapping or spawn errors (auto-hide after error_overlay_secs)
+        if let Some((ref msg, when)) = error_overlay {
+            if when.elapsed().as_secs() < config.error_overlay_secs.unwrap_or(3) {
+                canvas.set_draw_color(overlay_rgba);
+                let _ = canvas.fill_rect(Rect::new(0, 0, w as u32, h as u32));
+                // render message centered top
+                if let Ok(surface) = font.render(msg).blended(text_primary_c) {
+                    if let Ok(tex) = texture_creator.create_texture_from_surface(&surface) {
+                        let q = tex.query();
+                        let dst_x = (w as i32 - q.width as i32) / 2;
+                        let dst_y = (h as i32 - q.height as i32) / 2;
+                        let _ = canvas.copy(&tex, None, Rect::new(dst_x, dst_y, q.width, q.height));
+                    }
+                }
+            } else {
+                error_overlay = None;
+            }
+        }
+
+        // present moved after menu rendering so overlays are composed before presenting
+
+        // handle menu input and rendering after presenting main content
+        // menu state handling
+        let mut menu_next_state: Option<MenuState> = None;
+        match &mut menu_state {
+            MenuState::Closed => {}
+            MenuState::Open {
+                items,
+                selected: msel,
+            } => {
+                //println!(
+                //    "Rendering menu overlay, items={} selected={}",
+                //    items.len(),
+                //    msel
+                //);
+                // draw an opaque full-screen overlay so the menu is unmistakable
+                canvas.set_draw_color(menu_bg_c);
+                let _ = canvas.fill_rect(Rect::new(0, 0, w as u32, h as u32));
+
+                // menu box
+                let box_w = (w as i32) / 2;
+                let box_h = (items.len() as i32) * 28 + 40;
+                let box_x = (w as i32 - box_w) / 2;
+                let box_y = (h as i32 - box_h) / 2;
+                canvas.set_draw_color(menu_box_c);
+                let _ = canvas.fill_rect(Rect::new(box_x, box_y, box_w as u32, box_h as u32));
+
+                // Big MENU label
+                if let Ok(surf_big) = font.render("MENU").blended(menu_text_c) {
+                    if let Ok(tex_big) = texture_creator.create_texture_from_surface(&surf_big) {
+                        let qb = tex_big.query();
+                        let bx = box_x + 12;
+                        let by = box_y + 8;
+                        let _ = canvas.copy(&tex_big, None, Rect::new(bx, by, qb.width, qb.height));
+                    }
+                }
+
+                // title
+                if let Ok(surf) = font.render("Settings").blended(menu_title_c) {
+                    if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
+                        let q = tex.query();
+                        let _ = canvas.copy(
+                            &tex,
+                            None,
+                            Rect::new(box_x + 12, box_y + 8, q.width, q.height),
+                        );
+                    }
+                }
+
+                // render items
+                for (i, it) in items.iter().enumerate() {
+                    let y = box_y + 40 + (i as i32) * 28;
+                    if i == *msel {
+                        canvas.set_draw_color(menu_selected_c);
+                        let _ =
+                            canvas.fill_rect(Rect::new(box_x + 8, y - 4, (box_w - 16) as u32, 28));
+                    }
+                    let label = if it == "Toggle show_empty_systems" {
+                        let val = config.show_empty_systems.unwrap_or(false);
+                        format!("{}: {}", it, if val { "ON" } else { "OFF" })
+                    } else {
+                        it.clone()
+                    };
 
-        // handle global kill requests (from X11 hotkey)
-        if let Ok(_) = kill_rx.try_recv() {
-            let mut slot = current_child.lock().unwrap();
-            if let Some(ref mut c) = slot.as_mut() {
-                match c.kill() {
-                    Ok(_) => {
-                        menu_message = Some(("Killed emulator".to_string(), Instant::now()));
-                    }
-                    Err(e) => {
-                        menu_message = Some((format!("Kill failed: {}", e), Instant::now()));
+                    if let Ok(surf) = font.render(&label).blended(menu_text_c) {
+                        if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
+                            let q = tex.query();
+                            let _ = canvas.copy(
+                                &tex,
+                                None,
+                                Rect::new(box_x + 16, y, q.width, q.height),
+                            );
+                        }
                     }
                 }
-            } else {
-                menu_message = Some(("No emulator running".to_string(), Instant::now()));
-            }
-        }
 
-        // collect menu events when menu is open so main UI won't also react
-        let mut menu_events: Vec<sdl2::event::Event> = Vec::new();
+                // menu overlay will be presented once per frame at the end of the render pass
 
-        for event in event_pump.poll_iter() {
-            // If a menu or remap overlay is open, buffer events for the menu and skip main UI handling
-            if let MenuState::Open { .. } | MenuState::Remap { .. } = menu_state {
-                menu_events.push(event);
-                continue;
-            }
-            match event {
-                Event::Quit { .. } => break 'running,
-                // allow opening the menu with 'C' regardless of launching state
-                Event::KeyDown {
-                    keycode: Some(Keycode::C),
-                    ..
-                } => {
-                    let items = vec![
-                        "Toggle show_empty_systems".to_string(),
-                        "Remap controls".to_string(),
-                        "Reload config".to_string(),
-                        "Save config".to_string(),
-                        "Close".to_string(),
-                        "Exit".to_string(),
-                    ];
-                    menu_state = MenuState::Open { items, selected: 0 };
-                    // try to raise the SDL window so menu is visually on top
-                    let _ = canvas.window_mut().raise();
-                    println!("Menu opened (key C)");
-                }
-                // allow opening the menu with the controller Start button even when other guards exist
-                Event::ControllerButtonDown {
-                    button: CButton::Start,
-                    ..
-                } => {
-                    let items = vec![
-                        "Toggle show_empty_systems".to_string(),
-                        "Remap controls".to_string(),
-                        "Reload config".to_string(),
-                        "Save config".to_string(),
-                        "Close".to_string(),
-                        "Exit".to_string(),
-                    ];
-                    menu_state = MenuState::Open { items, selected: 0 };
-                    let _ = canvas.window_mut().raise();
-                    println!("Menu opened (controller Start)");
-                }
-                // joystick button events: map Start (common idx 7) to open menu; otherwise handle as joystick buttons
-                Event::JoyButtonDown { button_idx, .. } => {
-                    println!("Joystick button event idx: {}", button_idx);
-                    // typical mapping: Start often appears as button index 7 on some drivers
-                    if button_idx == 7 {
-                        let items = vec![
-                            "Toggle show_empty_systems".to_string(),
-                            "Remap controls".to_string(),
-                            "Reload config".to_string(),
-                            "Save config".to_string(),
-                            "Close".to_string(),
-                            "Exit".to_string(),
-                        ];
-                        menu_state = MenuState::Open { items, selected: 0 };
-                        let _ = canvas.window_mut().raise();
-                        println!("Menu opened (joy idx 7)");
-                        continue;
-                    }
-                    // if not launching, handle joystick button actions (fallback)
-                    if !launching {
-                        match button_idx {
-                            0 => {
-                                // common: A
-                                if let Some(rom_path) = current_roms.get(selected).cloned() {
-                                    if !systems_vec.is_empty() {
-                                        if let Some(s) =
-                                            systems_vec.get(current_system_idx).cloned()
-                                        {
-                                            if let Some(systems) = config.systems.as_ref() {
-                                                if let Some(t) = systems.get(&s) {
-                                                    launching = true;
-                                                    let tx = tx.clone();
-                                                    let t = t.clone();
-                                                    let child_slot = current_child.clone();
-                                                    thread::spawn(move || {
-                                                        spawn_emulator_template(
-                                                            &t, &rom_path, child_slot,
-                                                        );
-                                                        let _ = tx.send(());
-                                                    });
-                                                } else {
-                                                    error_overlay = Some((
-                                                        format!(
-                                                            "No emulator configured for system {}",
-                                                            s
-                                                        ),
-                                                        Instant::now(),
-                                                    ));
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
+                // process input for menu using the events collected earlier this frame
+                let mut activated: Option<String> = None;
+                for event in menu_events.drain(..) {
+                    match event {
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Up),
+                            ..
+                        }
+                        | Event::ControllerButtonDown {
+                            button: CButton::DPadUp,
+                            ..
+                        } => {
+                            *msel = msel.saturating_sub(1);
+                        }
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Down),
+                            ..
+                        }
+                        | Event::ControllerButtonDown {
+                            button: CButton::DPadDown,
+                            ..
+                        } => {
+                            if *msel + 1 < items.len() {
+                                *msel += 1;
                             }
-                            1 => { /* B button: back / cancel */ }
-                            _ => {}
                         }
+                        // Return, controller A, or joystick button 0 (common: A) select the item
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Return),
+                            ..
+                        }
+                        | Event::ControllerButtonDown {
+                            button: CButton::A, ..
+                        }
+                        | Event::JoyButtonDown { button_idx: 0, .. } => {
+                            activated = items.get(*msel).cloned();
+                            break;
+                        }
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Escape),
+                            ..
+                        }
+                        | Event::ControllerButtonDown {
+                            button: CButton::B, ..
+                        } => {
+                            menu_next_state = Some(MenuState::Closed);
+                        }
+                        _ => {}
                     }
                 }
-                // Escape: close menu if open, otherwise quit
-                Event::KeyDown {
-                    keycode: Some(Keycode::Escape),
-                    ..
-                } => match menu_state {
-                    MenuState::Open { .. } => {
-                        menu_state = MenuState::Closed;
-                    }
-                    _ => break 'running,
-                },
-                Event::KeyDown {
-                    keycode: Some(k), ..
-                } if !launching => {
-                    match k {
-                        Keycode::C => {
-                            // open settings menu (changed to 'C')
-                            let items = vec![
-                                "Toggle show_empty_systems".to_string(),
-                                "Remap controls".to_string(),
-                                "Reload config".to_string(),
-                                "Save config".to_string(),
-                                "Close".to_string(),
-                                "Exit".to_string(),
-                            ];
-                            menu_state = MenuState::Open { items, selected: 0 };
-                            println!("Menu opened (key C alt)");
-                        }
-                        Keycode::Left => {
-                            // switch to previous system
-                            if current_system_idx > 0 {
-                                current_system_idx -= 1;
-                            } else {
-                                current_system_idx = systems_vec.len().saturating_sub(1);
-                            }
-                            // update current roms and reset selection
-                            let cur = systems_vec.get(current_system_idx).cloned();
-                            current_roms = cur
-                                .as_ref()
-                                .and_then(|s| groups.get(s).cloned())
-                                .unwrap_or_default();
-                            selected = 0;
-                            scroll_offset = 0;
+                match activated.as_deref() {
+                    Some("Toggle show_empty_systems") => {
+                        let cur = config.show_empty_systems.unwrap_or(false);
+                        config.show_empty_systems = Some(!cur);
+                        let prev_system = systems_vec.get(current_system_idx).cloned();
+                        systems_vec = build_systems_vec(&config, &system_counts);
+                        current_system_idx = prev_system
+                            .as_ref()
+                            .and_then(|prev| systems_vec.iter().position(|s| s == prev))
+                            .unwrap_or(0);
+                        if at_system_list {
+                            current_roms = system_entries(&roms_dir, &systems_vec);
+                            (selected, scroll_offset) = move_selection(
+                                0,
+                                0,
+                                current_roms.len(),
+                                visible,
+                                current_system_idx as isize,
+                            );
                             text_textures.clear();
                             for _ in 0..current_roms.len() {
                                 text_textures.push(None);
                             }
-                        }
-                        Keycode::Right => {
-                            // switch to next system
-                            current_system_idx = (current_system_idx + 1) % systems_vec.len();
-                            let cur = systems_vec.get(current_system_idx).cloned();
-                            current_roms = cur
-                                .as_ref()
+                        } else if systems_vec.get(current_system_idx) != prev_system.as_ref() {
+                            // the current system was hidden: move to the first one shown
+                            current_roms = systems_vec
+                                .get(current_system_idx)
                                 .and_then(|s| groups.get(s).cloned())
                                 .unwrap_or_default();
                             selected = 0;
@@ -861,681 +4539,576 @@ fn main() -> Result<(), String> {
                                 text_textures.push(None);
                             }
                         }
-                        Keycode::Up => {
-                            if selected > 0 {
-                                selected -= 1;
-                                if selected < scroll_offset {
-                                    scroll_offset = selected;
-                                }
+                        menu_message = Some((
+                            format!("show_empty_systems set to {}", !cur),
+                            Instant::now(),
+                        ));
+                    }
+                    Some("Remap controller") => {
+                        let actions = vec![
+                            "A".to_string(),
+                            "B".to_string(),
+                            "UP".to_string(),
+                            "DOWN".to_string(),
+                            "LEFT".to_string(),
+                            "RIGHT".to_string(),
+                            "START".to_string(),
+                        ];
+                        menu_next_state = Some(MenuState::Remap {
+                            actions,
+                            idx: 0,
+                            temp_map: HashMap::new(),
+                            keyboard: false,
+                        });
+                    }
+                    Some("Remap keyboard") => {
+                        menu_next_state = Some(MenuState::Remap {
+                            actions: KEY_ACTIONS.iter().map(|(a, _)| a.to_string()).collect(),
+                            idx: 0,
+                            temp_map: HashMap::new(),
+                            keyboard: true,
+                        });
+                    }
+                    Some(label @ ("Reload config" | "Rescan ROMs")) => {
+                        rescan_request = Some(label == "Reload config");
+                    }
+                    Some("Find duplicates") => {
+                        let msg = if dupes_running {
+                            "Already finding duplicates"
+                        } else {
+                            dupes::find_in_background(
+                                groups.clone(),
+                                config.duplicate_hash.unwrap_or(false),
+                                dupes_tx.clone(),
+                            );
+                            dupes_running = true;
+                            "Finding duplicates..."
+                        };
+                        menu_message = Some((msg.to_string(), Instant::now()));
+                    }
+                    Some("Scrape metadata") => {
+                        let sys = systems_vec.get(current_system_idx).cloned();
+                        match (&config.scraper_program, sys) {
+                            _ if scrape_status.is_some() => {
+                                menu_message = Some((
+                                    "Scraper is already running".to_string(),
+                                    Instant::now(),
+                                ));
+                            }
+                            (Some(program), Some(sys)) => {
+                                scrape::run_in_background(
+                                    program.clone(),
+                                    config.scraper_args.clone().unwrap_or_default(),
+                                    sys.clone(),
+                                    groups.get(&sys).cloned().unwrap_or_default(),
+                                    scrape_tx.clone(),
+                                );
+                                scrape_status = Some(format!(
+                                    "Scraping {}...",
+                                    system_display_name(&config, &sys)
+                                ));
+                                menu_next_state = Some(MenuState::Closed);
                             }
+                            _ => {}
                         }
-                        Keycode::Down => {
-                            if selected + 1 < current_roms.len() {
-                                selected += 1;
-                                let visible = ((h as i32 - 60) / (TILE_H + 10)) as usize;
-                                if selected >= scroll_offset + visible {
-                                    scroll_offset = selected - visible + 1;
-                                }
+                    }
+                    Some("Save config") => {
+                        if let Err(e) = write_config(&config, config_path.as_deref()) {
+                            menu_message = Some((format!("Save failed: {}", e), Instant::now()));
+                        } else {
+                            menu_message = Some(("Config saved".to_string(), Instant::now()));
+                        }
+                    }
+                    Some("Cycle theme") => {
+                        theme_idx = (theme_idx + 1) % style::PRESETS.len();
+                        let name = style::PRESETS[theme_idx];
+                        style::apply_preset(&mut style, name);
+                        style::apply_preset(&mut saved_style, name);
+                        palette = style::Palette::from_style(&style);
+                        // cached tile text was rendered in the old colors
+                        for t in text_textures.iter_mut() {
+                            *t = None;
+                        }
+                        badge_textures.clear();
+                        menu_message = Some((format!("Theme: {}", name), Instant::now()));
+                    }
+                    Some("Save style") => {
+                        if let Err(e) = style::write_style(&saved_style) {
+                            menu_message = Some((format!("Save failed: {}", e), Instant::now()));
+                        } else {
+                            menu_message = Some(("Style saved".to_string(), Instant::now()));
+                        }
+                    }
+                    Some("Close") => {
+                        menu_next_state = Some(MenuState::Closed);
+                    }
+                    Some("Exit") => {
+                        should_quit = true;
+                        menu_next_state = Some(MenuState::Closed);
+                    }
+                    _ => {}
+                }
+                // apply any pending menu state change
+                if let Some(s) = menu_next_state {
+                    menu_state = s;
+                }
+                // If an Exit was chosen in the menu, break out of main loop
+                if should_quit {
+                    break 'running;
+                }
+            }
+            MenuState::Remap {
+                actions,
+                idx,
+                temp_map,
+                keyboard,
+            } => {
+                // draw remap overlay
+                canvas.set_draw_color(overlay_rgba);
+                let _ = canvas.fill_rect(Rect::new(0, 0, w as u32, h as u32));
+                let prompt = format!(
+                    "Press a {} for: {}",
+                    if *keyboard {
+                        "key (Esc cancels)"
+                    } else {
+                        "button"
+                    },
+                    actions.get(*idx).unwrap_or(&"".to_string())
+                );
+                if let Ok(surf) = font.render(&prompt).blended(text_primary_c) {
+                    if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
+                        let q = tex.query();
+                        let dst_x = ((w as i32) - q.width as i32) / 2;
+                        let dst_y = (h as i32) / 2;
+                        let _ = canvas.copy(&tex, None, Rect::new(dst_x, dst_y, q.width, q.height));
+                    }
+                }
+                // e.g. why the last key was refused
+                if let Some((msg, _)) = &menu_message {
+                    if let Ok(surf) = font.render(msg).blended(text_primary_c) {
+                        if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
+                            let q = tex.query();
+                            let dst_x = (w - q.width as i32) / 2;
+                            let dst_y = h / 2 + 2 * q.height as i32;
+                            let _ =
+                                canvas.copy(&tex, None, Rect::new(dst_x, dst_y, q.width, q.height));
+                        }
+                    }
+                }
+                canvas.present();
+
+                // capture one event for remapping
+                let mut cancelled = false;
+                if let Some(evt) = event_pump.wait_event_timeout(3000) {
+                    match evt {
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Escape),
+                            ..
+                        } if *keyboard => {
+                            cancelled = true;
+                        }
+                        Event::KeyDown {
+                            keycode: Some(k),
+                            repeat: false,
+                            ..
+                        } if *keyboard => {
+                            // one key per action; a key already taken asks again for this one
+                            let taken = temp_map.iter().find(|(_, name)| **name == k.name());
+                            if let Some((other, _)) = taken {
+                                menu_message = Some((
+                                    format!("{} is already used for {}", k.name(), other),
+                                    Instant::now(),
+                                ));
+                            } else if let Some(act) = actions.get(*idx).cloned() {
+                                temp_map.insert(act, k.name());
+                                menu_message = None;
+                                *idx += 1;
                             }
                         }
-                        Keycode::W => {
-                            // toggle fullscreen/windowed for debugging
-                            if is_fullscreen {
-                                let _ = canvas.window_mut().set_fullscreen(FullscreenType::Off);
-                                is_fullscreen = false;
-                                println!("Toggled windowed mode");
-                            } else {
-                                let _ = canvas.window_mut().set_fullscreen(FullscreenType::Desktop);
-                                is_fullscreen = true;
-                                println!("Toggled fullscreen mode");
+                        Event::ControllerButtonDown { button, .. } if !*keyboard => {
+                            let key = format!("controller:{:?}", button);
+                            if let Some(act) = actions.get(*idx).cloned() {
+                                temp_map.insert(act, key);
+                                *idx += 1;
                             }
                         }
-                        Keycode::Return => {
-                            if let Some(rom_path) = current_roms.get(selected).cloned() {
-                                let sys = systems_vec.get(current_system_idx).cloned();
-                                if let Some(s) = sys {
-                                    if let Some(systems) = config.systems.as_ref() {
-                                        if let Some(t) = systems.get(&s) {
-                                            launching = true;
-                                            let tx = tx.clone();
-                                            let t = t.clone();
-                                            let child_slot = current_child.clone();
-                                            thread::spawn(move || {
-                                                spawn_emulator_template(&t, &rom_path, child_slot);
-                                                let _ = tx.send(());
-                                            });
-                                        } else {
-                                            // fallback: try resolve by extension across systems
-                                            if let Some(ext) =
-                                                rom_path.extension().and_then(|s| s.to_str())
-                                            {
-                                                let ext_l = ext.to_lowercase();
-                                                if let Some(found_sys) = find_system_for_extension(
-                                                    &ext_l,
-                                                    &config,
-                                                    &systems_vec,
-                                                ) {
-                                                    if let Some(found_t) = config
-                                                        .systems
-                                                        .as_ref()
-                                                        .and_then(|m| m.get(&found_sys))
-                                                    {
-                                                        launching = true;
-                                                        let tx = tx.clone();
-                                                        let t = found_t.clone();
-                                                        let child_slot = current_child.clone();
-                                                        thread::spawn(move || {
-                                                            spawn_emulator_template(
-                                                                &t, &rom_path, child_slot,
-                                                            );
-                                                            let _ = tx.send(());
-                                                        });
-                                                    } else {
-                                                        error_overlay = Some((format!("No emulator configured for system {}", found_sys), Instant::now()));
-                                                    }
-                                                } else {
-                                                    error_overlay = Some((
-                                                        format!(
-                                                            "No emulator configured for system {}",
-                                                            s
-                                                        ),
-                                                        Instant::now(),
-                                                    ));
-                                                }
-                                            } else {
-                                                error_overlay = Some((
-                                                    format!(
-                                                        "No emulator configured for system {}",
-                                                        s
-                                                    ),
-                                                    Instant::now(),
-                                                ));
-                                            }
-                                        }
-                                    }
-                                }
+                        Event::JoyButtonDown { button_idx, .. } if !*keyboard => {
+                            let key = format!("joybutton:{}", button_idx);
+                            if let Some(act) = actions.get(*idx).cloned() {
+                                temp_map.insert(act, key);
+                                *idx += 1;
                             }
                         }
                         _ => {}
                     }
                 }
-                // (Escape to quit is handled above)
-                Event::ControllerButtonDown { button, .. } if !launching => {
-                    println!("Controller button event: {:?}", button);
-                    match button {
-                        CButton::Start => {
-                            // open settings menu
-                            let items = vec![
-                                "Toggle show_empty_systems".to_string(),
-                                "Remap controls".to_string(),
-                                "Reload config".to_string(),
-                                "Save config".to_string(),
-                                "Close".to_string(),
-                                "Exit".to_string(),
-                            ];
-                            menu_state = MenuState::Open { items, selected: 0 };
-                            println!("Menu opened (controller Start alt)");
+
+                // finish
+                if cancelled {
+                    menu_message = Some(("Keyboard remap cancelled".to_string(), Instant::now()));
+                    menu_state = MenuState::Closed;
+                } else if *idx >= actions.len() {
+                    // commit to config
+                    let saved = if *keyboard {
+                        config.keyboard_map = Some(temp_map.clone());
+                        key_map = key_translation(&config);
+                        "Keyboard mapping saved"
+                    } else {
+                        config.controller_map = Some(temp_map.clone());
+                        "Controller mapping saved"
+                    };
+                    if let Err(e) = write_config(&config, config_path.as_deref()) {
+                        menu_message = Some((format!("Save failed: {}", e), Instant::now()));
+                    } else {
+                        menu_message = Some((saved.to_string(), Instant::now()));
+                    }
+                    menu_state = MenuState::Closed;
+                }
+            }
+            MenuState::DiscSelect {
+                template,
+                discs,
+                selected: dsel,
+            } => {
+                canvas.set_draw_color(overlay_rgba);
+                let _ = canvas.fill_rect(Rect::new(0, 0, w as u32, h as u32));
+
+                let box_w = w / 2;
+                let box_h = (discs.len() as i32) * 28 + 40;
+                let box_x = (w - box_w) / 2;
+                let box_y = (h - box_h) / 2;
+                canvas.set_draw_color(menu_box_c);
+                let _ = canvas.fill_rect(Rect::new(box_x, box_y, box_w as u32, box_h as u32));
+
+                if let Ok(surf) = font.render("Select disc").blended(menu_title_c) {
+                    if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
+                        let q = tex.query();
+                        let _ = canvas.copy(
+                            &tex,
+                            None,
+                            Rect::new(box_x + 12, box_y + 8, q.width, q.height),
+                        );
+                    }
+                }
+
+                for (i, disc) in discs.iter().enumerate() {
+                    let y = box_y + 40 + (i as i32) * 28;
+                    if i == *dsel {
+                        canvas.set_draw_color(menu_selected_c);
+                        let _ =
+                            canvas.fill_rect(Rect::new(box_x + 8, y - 4, (box_w - 16) as u32, 28));
+                    }
+                    let label = disc
+                        .file_name()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or("")
+                        .to_string();
+                    if let Ok(surf) = font.render(&label).blended(menu_text_c) {
+                        if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
+                            let q = tex.query();
+                            let _ = canvas.copy(
+                                &tex,
+                                None,
+                                Rect::new(box_x + 16, y, q.width, q.height),
+                            );
+                        }
+                    }
+                }
+
+                // None = keep open, Some(None) = cancel, Some(Some(i)) = launch disc i
+                let mut choice: Option<Option<usize>> = None;
+                for event in menu_events.drain(..) {
+                    match event {
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Up),
+                            ..
                         }
-                        CButton::DPadLeft => {
-                            if current_system_idx > 0 {
-                                current_system_idx -= 1;
-                            } else {
-                                current_system_idx = systems_vec.len().saturating_sub(1);
-                            }
-                            let cur = systems_vec.get(current_system_idx).cloned();
-                            current_roms = cur
-                                .as_ref()
-                                .and_then(|s| groups.get(s).cloned())
-                                .unwrap_or_default();
-                            selected = 0;
-                            scroll_offset = 0;
-                            text_textures.clear();
-                            for _ in 0..current_roms.len() {
-                                text_textures.push(None);
-                            }
+                        | Event::ControllerButtonDown {
+                            button: CButton::DPadUp,
+                            ..
+                        } => {
+                            *dsel = dsel.saturating_sub(1);
                         }
-                        CButton::DPadRight => {
-                            current_system_idx = (current_system_idx + 1) % systems_vec.len();
-                            let cur = systems_vec.get(current_system_idx).cloned();
-                            current_roms = cur
-                                .as_ref()
-                                .and_then(|s| groups.get(s).cloned())
-                                .unwrap_or_default();
-                            selected = 0;
-                            scroll_offset = 0;
-                            text_textures.clear();
-                            for _ in 0..current_roms.len() {
-                                text_textures.push(None);
-                            }
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Down),
+                            ..
                         }
-                        CButton::DPadUp => {
-                            if selected > 0 {
-                                selected -= 1;
-                                if selected < scroll_offset {
-                                    scroll_offset = selected;
-                                }
-                            }
+                        | Event::ControllerButtonDown {
+                            button: CButton::DPadDown,
+                            ..
+                        } => {
+                            *dsel = (*dsel + 1).min(discs.len() - 1);
                         }
-                        CButton::DPadDown => {
-                            if selected + 1 < current_roms.len() {
-                                selected += 1;
-                                let visible = ((h as i32 - 60) / (TILE_H + 10)) as usize;
-                                if selected >= scroll_offset + visible {
-                                    scroll_offset = selected - visible + 1;
-                                }
-                            }
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Return),
+                            ..
                         }
-                        CButton::A => {
-                            if let Some(rom_path) = current_roms.get(selected).cloned() {
-                                if let Some(s) = systems_vec.get(current_system_idx).cloned() {
-                                    if let Some(systems) = config.systems.as_ref() {
-                                        if let Some(t) = systems.get(&s) {
-                                            launching = true;
-                                            let tx = tx.clone();
-                                            let t = t.clone();
-                                            let child_slot = current_child.clone();
-                                            thread::spawn(move || {
-                                                spawn_emulator_template(&t, &rom_path, child_slot);
-                                                let _ = tx.send(());
-                                            });
-                                        } else {
-                                            if let Some(ext) =
-                                                rom_path.extension().and_then(|s| s.to_str())
-                                            {
-                                                let ext_l = ext.to_lowercase();
-                                                if let Some(found_sys) = find_system_for_extension(
-                                                    &ext_l,
-                                                    &config,
-                                                    &systems_vec,
-                                                ) {
-                                                    if let Some(found_t) = config
-                                                        .systems
-                                                        .as_ref()
-                                                        .and_then(|m| m.get(&found_sys))
-                                                    {
-                                                        launching = true;
-                                                        let tx = tx.clone();
-                                                        let t = found_t.clone();
-                                                        let child_slot = current_child.clone();
-                                                        thread::spawn(move || {
-                                                            spawn_emulator_template(
-                                                                &t, &rom_path, child_slot,
-                                                            );
-                                                            let _ = tx.send(());
-                                                        });
-                                                    } else {
-                                                        error_overlay = Some((format!("No emulator configured for system {}", found_sys), Instant::now()));
-                                                    }
-                                                } else {
-                                                    error_overlay = Some((
-                                                        format!(
-                                                            "No emulator configured for system {}",
-                                                            s
-                                                        ),
-                                                        Instant::now(),
-                                                    ));
-                                                }
-                                            } else {
-                                                error_overlay = Some((
-                                                    format!(
-                                                        "No emulator configured for system {}",
-                                                        s
-                                                    ),
-                                                    Instant::now(),
-                                                ));
-                                            }
-                                        }
-                                    }
-                                }
-                            }
+                        | Event::ControllerButtonDown {
+                            button: CButton::A, ..
+                        }
+                        | Event::JoyButtonDown { button_idx: 0, .. } => {
+                            choice = Some(Some(*dsel));
+                        }
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Escape),
+                            ..
                         }
-                        CButton::B => {
-                            // placeholder: could go back from detail view
+                        | Event::ControllerButtonDown {
+                            button: CButton::B, ..
+                        } => {
+                            choice = Some(None);
                         }
+                        Event::Quit { .. } => break 'running,
                         _ => {}
                     }
                 }
-
-                Event::JoyAxisMotion {
-                    axis_idx, value, ..
-                } if !launching => {
-                    // axis_idx: 0 = left X, 1 = left Y
-                    const AXIS_THRESHOLD: i16 = 16000;
-                    if axis_idx == 0 {
-                        // left/right switch systems
-                        if value < -AXIS_THRESHOLD {
-                            if !systems_vec.is_empty() {
-                                if current_system_idx > 0 {
-                                    current_system_idx -= 1;
-                                } else {
-                                    current_system_idx = systems_vec.len().saturating_sub(1);
-                                }
-                                let cur = systems_vec.get(current_system_idx).cloned();
-                                current_roms = cur
-                                    .as_ref()
-                                    .and_then(|s| groups.get(s).cloned())
-                                    .unwrap_or_default();
-                                selected = 0;
-                                scroll_offset = 0;
-                                text_textures.clear();
-                                for _ in 0..current_roms.len() {
-                                    text_textures.push(None);
-                                }
-                            }
-                        } else if value > AXIS_THRESHOLD {
-                            if !systems_vec.is_empty() {
-                                current_system_idx = (current_system_idx + 1) % systems_vec.len();
-                                let cur = systems_vec.get(current_system_idx).cloned();
-                                current_roms = cur
-                                    .as_ref()
-                                    .and_then(|s| groups.get(s).cloned())
-                                    .unwrap_or_default();
-                                selected = 0;
-                                scroll_offset = 0;
-                                text_textures.clear();
-                                for _ in 0..current_roms.len() {
-                                    text_textures.push(None);
-                                }
-                            }
-                        }
-                    } else if axis_idx == 1 {
-                        // up/down navigate list
-                        if value < -AXIS_THRESHOLD {
-                            if selected > 0 {
-                                selected -= 1;
-                                if selected < scroll_offset {
-                                    scroll_offset = selected;
-                                }
-                            }
-                        } else if value > AXIS_THRESHOLD {
-                            if selected + 1 < current_roms.len() {
-                                selected += 1;
-                                let visible = ((h as i32 - 60) / (TILE_H + 10)) as usize;
-                                if selected >= scroll_offset + visible {
-                                    scroll_offset = selected - visible + 1;
-                                }
-                            }
+                match choice {
+                    Some(Some(i)) => {
+                        if let Some(disc) = discs.get(i).cloned() {
+                            ready_launch = Some((template.as_ref().clone(), disc));
                         }
+                        menu_state = MenuState::Closed;
                     }
+                    Some(None) => menu_state = MenuState::Closed,
+                    None => {}
                 }
-                // Menu input handling (when menu is open)
-                // Note: we keep it simple and handle key/controller events in the main loop below when rendering the menu
-                _ => {}
             }
-        }
-
-        // render
-        canvas.set_draw_color(bg_color);
-        canvas.clear();
-
-        // list layout (single column). compute tile sizes and visible window
-        let padding = 10;
-        let start_x = padding;
-        let start_y = padding + 44; // leave space for banner
-        let tile_w = (w as i32) - (padding * 2);
-        let tile_h = TILE_H;
-
-        let available_h = (h as i32) - start_y - padding;
-        let visible = (available_h / (tile_h + padding)).max(1) as usize;
-
-        // ensure scroll offset valid
-        if scroll_offset >= current_roms.len() && !current_roms.is_empty() {
-            scroll_offset = current_roms.len() - 1;
-        }
-
-        for (idx, rom) in current_roms
-            .iter()
-            .enumerate()
-            .skip(scroll_offset)
-            .take(visible)
-        {
-            let i = idx;
-            let x = start_x;
-            let y = start_y + ((i - scroll_offset) as i32) * (tile_h + padding);
-            let rect = Rect::new(x, y, tile_w as u32, tile_h as u32);
+            MenuState::Confirm {
+                title,
+                options,
+                action,
+                selected: ksel,
+            } => {
+                canvas.set_draw_color(overlay_rgba);
+                let _ = canvas.fill_rect(Rect::new(0, 0, w as u32, h as u32));
 
-            if i == selected {
-                canvas.set_draw_color(tile_selected_c);
-            } else {
-                canvas.set_draw_color(tile_normal_c);
-            }
-            let _ = canvas.fill_rect(rect);
+                let box_w = w / 2;
+                let box_h = (options.len() as i32) * 28 + 72;
+                let box_x = (w - box_w) / 2;
+                let box_y = (h - box_h) / 2;
+                canvas.set_draw_color(menu_box_c);
+                let _ = canvas.fill_rect(Rect::new(box_x, box_y, box_w as u32, box_h as u32));
 
-            // filename text rendering (lazy create texture)
-            if text_textures.get(i).and_then(|t| t.as_ref()).is_none() {
-                if let Some(name) = rom.file_name().and_then(|s| s.to_str()) {
-                    // Render filename into up to 2 lines. If too long, truncate the second line with ellipsis.
-                    let padding = 8; // px padding inside tile
-                                     // use current list tile width, not the old TILE_W constant
-                    let max_w = (tile_w as u32).saturating_sub((padding * 2) as u32);
+                if let Ok(surf) = font.render(title).blended(menu_title_c) {
+                    if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
+                        let q = tex.query();
+                        let _ = canvas.copy(
+                            &tex,
+                            None,
+                            Rect::new(box_x + 12, box_y + 8, q.width, q.height),
+                        );
+                    }
+                }
 
-                    // Helper to measure width using the font
-                    let width_of =
-                        |s: &str| -> u32 { font.size_of(s).map(|(w, _)| w).unwrap_or(0) };
+                for (i, label) in options.iter().enumerate() {
+                    let y = box_y + 72 + (i as i32) * 28;
+                    if i == *ksel {
+                        canvas.set_draw_color(menu_selected_c);
+                        let _ =
+                            canvas.fill_rect(Rect::new(box_x + 8, y - 4, (box_w - 16) as u32, 28));
+                    }
+                    if let Ok(surf) = font.render(label).blended(menu_text_c) {
+                        if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
+                            let q = tex.query();
+                            let _ = canvas.copy(
+                                &tex,
+                                None,
+                                Rect::new(box_x + 16, y, q.width, q.height),
+                            );
+                        }
+                    }
+                }
 
-                    // If fits in one line, use that
-                    if width_of(name) <= max_w {
-                        if let Ok(surface) = font.render(name).blended(text_primary_c) {
-                            if let Ok(tex) = texture_creator.create_texture_from_surface(&surface) {
-                                if let Some(slot) = text_textures.get_mut(i) {
-                                    *slot = Some(vec![tex]);
-                                }
-                            }
+                // None = undecided, Some(true) = yes, Some(false) = no
+                let mut answer: Option<bool> = None;
+                for event in menu_events.drain(..) {
+                    match event {
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Up),
+                            ..
                         }
-                    } else {
-                        // find maximal prefix that fits on first line (binary search)
-                        let chars: Vec<char> = name.chars().collect();
-                        let mut lo = 0usize;
-                        let mut hi = chars.len();
-                        while lo < hi {
-                            let mid = (lo + hi + 1) / 2;
-                            let cand: String = chars.iter().take(mid).collect();
-                            if width_of(&cand) <= max_w {
-                                lo = mid;
-                            } else {
-                                hi = mid - 1;
-                            }
+                        | Event::ControllerButtonDown {
+                            button: CButton::DPadUp,
+                            ..
+                        } => {
+                            *ksel = ksel.saturating_sub(1);
                         }
-                        let mut first: String = chars.iter().take(lo).collect();
-                        let remaining: String = chars.iter().skip(lo).collect();
-
-                        // Try to smart-split at the last separator within the first line
-                        let seps = [' ', '-', ':', '_'];
-                        if let Some(pos) = first.rfind(|c: char| seps.contains(&c)) {
-                            // split at separator pos (exclude separator)
-                            let new_first: String = first.chars().take(pos).collect();
-                            if !new_first.is_empty() {
-                                // remaining becomes text after separator plus old remaining
-                                let after_sep: String =
-                                    first.chars().skip(pos + 1).collect::<String>() + &remaining;
-                                first = new_first;
-                                // use after_sep as the new remaining
-                                let remaining = after_sep;
-                                // proceed to render second line based on new remaining
-                                // determine second line below using 'remaining'
-                                // For scope reasons we shadow the name 'remaining' by reassigning below via let
-                                let remaining = remaining;
-
-                                // Now create second line from remaining (fits or truncated)
-                                let second = if width_of(&remaining) <= max_w {
-                                    remaining
-                                } else {
-                                    // truncate with ellipsis at end
-                                    let ell = "...";
-                                    let mut lo2 = 0usize;
-                                    let mut hi2 = remaining.chars().count();
-                                    while lo2 < hi2 {
-                                        let mid = (lo2 + hi2 + 1) / 2;
-                                        let cand: String =
-                                            remaining.chars().take(mid).collect::<String>() + ell;
-                                        if width_of(&cand) <= max_w {
-                                            lo2 = mid;
-                                        } else {
-                                            hi2 = mid - 1;
-                                        }
-                                    }
-                                    let kept: String = remaining.chars().take(lo2).collect();
-                                    if kept.is_empty() {
-                                        ell.to_string()
-                                    } else {
-                                        kept + ell
-                                    }
-                                };
-
-                                // render both lines
-                                let mut line_texts: Vec<Texture> = Vec::new();
-                                if let Ok(s1) = font.render(&first).blended(text_primary_c) {
-                                    if let Ok(t1) = texture_creator.create_texture_from_surface(&s1)
-                                    {
-                                        line_texts.push(t1);
-                                    }
-                                }
-                                if let Ok(s2) = font.render(&second).blended(text_primary_c) {
-                                    if let Ok(t2) = texture_creator.create_texture_from_surface(&s2)
-                                    {
-                                        line_texts.push(t2);
-                                    }
-                                }
-                                if let Some(slot) = text_textures.get_mut(i) {
-                                    *slot = Some(line_texts);
-                                }
-                                continue;
-                            }
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Down),
+                            ..
                         }
-
-                        // Fallback behavior: second line is remaining, possibly truncated with ellipsis
-                        let second = if width_of(&remaining) <= max_w {
-                            remaining.clone()
-                        } else {
-                            let ell = "...";
-                            let mut lo2 = 0usize;
-                            let mut hi2 = remaining.chars().count();
-                            while lo2 < hi2 {
-                                let mid = (lo2 + hi2 + 1) / 2;
-                                let cand: String =
-                                    remaining.chars().take(mid).collect::<String>() + ell;
-                                if width_of(&cand) <= max_w {
-                                    lo2 = mid;
-                                } else {
-                                    hi2 = mid - 1;
-                                }
-                            }
-                            let kept: String = remaining.chars().take(lo2).collect();
-                            if kept.is_empty() {
-                                ell.to_string()
-                            } else {
-                                kept + ell
-                            }
-                        };
-
-                        // render both lines
-                        let mut line_texts: Vec<Texture> = Vec::new();
-                        if let Ok(s1) = font.render(&first).blended(text_primary_c) {
-                            if let Ok(t1) = texture_creator.create_texture_from_surface(&s1) {
-                                line_texts.push(t1);
-                            }
+                        | Event::ControllerButtonDown {
+                            button: CButton::DPadDown,
+                            ..
+                        } => {
+                            *ksel = (*ksel + 1).min(options.len() - 1);
                         }
-                        if let Ok(s2) = font.render(&second).blended(text_primary_c) {
-                            if let Ok(t2) = texture_creator.create_texture_from_surface(&s2) {
-                                line_texts.push(t2);
-                            }
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Return),
+                            ..
+                        }
+                        | Event::ControllerButtonDown {
+                            button: CButton::A, ..
+                        }
+                        | Event::JoyButtonDown { button_idx: 0, .. } => {
+                            answer = Some(*ksel == 0);
+                        }
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Escape),
+                            ..
                         }
-                        if let Some(slot) = text_textures.get_mut(i) {
-                            *slot = Some(line_texts);
+                        | Event::ControllerButtonDown {
+                            button: CButton::B, ..
+                        } => {
+                            answer = Some(false);
                         }
+                        Event::Quit { .. } => break 'running,
+                        _ => {}
                     }
                 }
-            }
-
-            if let Some(Some(text_vec)) = text_textures.get(i) {
-                // draw one or two lines centered vertically in the tile
-                let mut total_h = 0i32;
-                let mut queries: Vec<sdl2::render::TextureQuery> = Vec::new();
-                for tex in text_vec.iter() {
-                    let q = tex.query();
-                    total_h += q.height as i32;
-                    queries.push(q);
-                }
-                // spacing between lines
-                let spacing = 2;
-                total_h += spacing * ((queries.len() as i32) - 1).max(0);
-                let mut cursor_y = y + (tile_h - total_h) / 2; // center vertically
-                for (idx, tex) in text_vec.iter().enumerate() {
-                    let q = &queries[idx];
-                    let tex_w = q.width as i32;
-                    let tex_h = q.height as i32;
-                    let dst_x = x + (tile_w - tex_w) / 2;
-                    let dst_y = cursor_y;
-                    let _ = canvas.copy(
-                        tex,
-                        None,
-                        Rect::new(dst_x, dst_y, tex_w as u32, tex_h as u32),
-                    );
-                    cursor_y += tex_h + spacing;
+                match answer {
+                    Some(true) => {
+                        match action {
+                            ConfirmAction::KillEmulator => {
+                                menu_message = Some((
+                                    emu::kill_current_emulator(&current_child),
+                                    Instant::now(),
+                                ));
+                            }
+                            ConfirmAction::Launch(t, rom) => {
+                                pending_launch = Some((t.as_ref().clone(), rom.clone()));
+                            }
+                        }
+                        menu_state = MenuState::Closed;
+                    }
+                    Some(false) => menu_state = MenuState::Closed,
+                    None => {}
                 }
             }
-        }
-
-        // banner
-        canvas.set_draw_color(banner_bg_c);
-        let _ = canvas.fill_rect(Rect::new(0, 0, w as u32, 40));
-
-        // render banner text: current system and selected filename + mapped emulator
-        let current_system_name = systems_vec
-            .get(current_system_idx)
-            .cloned()
-            .unwrap_or_else(|| "".to_string());
-        // show system name + count
-        let count = current_roms.len();
-        let system_label = format!("{} ({})", current_system_name.to_uppercase(), count);
-        if let Ok(surf_sys) = font.render(&system_label).blended(banner_text_c) {
-            if let Ok(tex_sys) = texture_creator.create_texture_from_surface(&surf_sys) {
-                let q = tex_sys.query();
-                // position system label at the right side of banner to avoid overlapping centered filename
-                let dst_x = (w as i32) - (q.width as i32) - 12;
-                let dst_y = 8;
-                let _ = canvas.copy(&tex_sys, None, Rect::new(dst_x, dst_y, q.width, q.height));
-            }
-        }
-
-        if let Some(rom_path) = current_roms.get(selected) {
-            if let Some(name) = rom_path.file_name().and_then(|s| s.to_str()) {
-                // emulator mapping name
-                let emu_name = config
-                    .systems
-                    .as_ref()
-                    .and_then(|m| m.get(&current_system_name))
-                    .map(|t| t.program.clone())
-                    .or_else(|| config.default.as_ref().map(|d| d.program.clone()));
+            MenuState::EmulatorSelect {
+                rom,
+                choices,
+                selected: esel,
+            } => {
+                canvas.set_draw_color(overlay_rgba);
+                let _ = canvas.fill_rect(Rect::new(0, 0, w as u32, h as u32));
 
-                // prepare filename display: if too wide, do middle elide keeping start and end
-                let banner_padding = 12u32;
-                let avail = (w as u32).saturating_sub(banner_padding * 2);
-                let full_name = name.to_string();
-                let display_name = if font.size_of(&full_name).map(|(w, _)| w).unwrap_or(0) <= avail
-                {
-                    full_name.clone()
-                } else {
-                    // middle elide
-                    fn elide_middle(s: &str, max_chars: usize) -> String {
-                        let chars: Vec<char> = s.chars().collect();
-                        if chars.len() <= max_chars {
-                            return s.to_string();
-                        }
-                        if max_chars <= 3 {
-                            return "...".to_string();
-                        }
-                        let keep = (max_chars - 3) / 2;
-                        let head = keep + ((max_chars - 3) % 2);
-                        let tail = keep;
-                        let start: String = chars.iter().take(head).collect();
-                        let end: String = chars
-                            .iter()
-                            .rev()
-                            .take(tail)
-                            .collect::<Vec<&char>>()
-                            .into_iter()
-                            .rev()
-                            .collect();
-                        format!("{}...{}", start, end)
-                    }
-                    // estimate max chars fitting in avail using avg char width of 7
-                    let est = ((avail as f32) / 7.0) as usize;
-                    elide_middle(&full_name, est.max(8))
-                };
+                let box_w = w / 2;
+                let box_h = (choices.len() as i32) * 28 + 40;
+                let box_x = (w - box_w) / 2;
+                let box_y = (h - box_h) / 2;
+                canvas.set_draw_color(menu_box_c);
+                let _ = canvas.fill_rect(Rect::new(box_x, box_y, box_w as u32, box_h as u32));
 
-                if let Ok(surf) = font.render(&display_name).blended(banner_text_c) {
+                if let Ok(surf) = font.render("Launch with").blended(menu_title_c) {
                     if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
                         let q = tex.query();
-                        let dst_x = ((w as i32) - q.width as i32) / 2;
-                        let dst_y = 8;
-                        let _ = canvas.copy(&tex, None, Rect::new(dst_x, dst_y, q.width, q.height));
+                        let _ = canvas.copy(
+                            &tex,
+                            None,
+                            Rect::new(box_x + 12, box_y + 8, q.width, q.height),
+                        );
                     }
                 }
 
-                if let Some(emu) = emu_name {
-                    let emu_txt = format!("emu: {}", emu);
-                    if let Ok(surf2) = font.render(&emu_txt).blended(emu_text_c) {
-                        if let Ok(tex2) = texture_creator.create_texture_from_surface(&surf2) {
-                            let q2 = tex2.query();
-                            let dst_x2 = 12;
-                            let dst_y2 = 10;
+                for (i, choice) in choices.iter().enumerate() {
+                    let y = box_y + 40 + (i as i32) * 28;
+                    if i == *esel {
+                        canvas.set_draw_color(menu_selected_c);
+                        let _ =
+                            canvas.fill_rect(Rect::new(box_x + 8, y - 4, (box_w - 16) as u32, 28));
+                    }
+                    if let Ok(surf) = font.render(&emulator_label(choice)).blended(menu_text_c) {
+                        if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
+                            let q = tex.query();
                             let _ = canvas.copy(
-                                &tex2,
+                                &tex,
                                 None,
-                                Rect::new(dst_x2, dst_y2, q2.width, q2.height),
+                                Rect::new(box_x + 16, y, q.width, q.height),
                             );
                         }
                     }
                 }
-            }
-        }
-
-        // launching overlay
-        if launching {
-            canvas.set_draw_color(overlay_rgba);
-            let _ = canvas.fill_rect(Rect::new(0, 0, w as u32, h as u32));
-        }
-
-        // error overlay for missing mapping or spawn errors (auto-hide after 3s)
-        if let Some((ref msg, when)) = error_overlay {
-            if when.elapsed().as_secs() < 3 {
-                canvas.set_draw_color(overlay_rgba);
-                let _ = canvas.fill_rect(Rect::new(0, 0, w as u32, h as u32));
-                // render message centered top
-                if let Ok(surface) = font.render(msg).blended(text_primary_c) {
-                    if let Ok(tex) = texture_creator.create_texture_from_surface(&surface) {
-                        let q = tex.query();
-                        let dst_x = (w as i32 - q.width as i32) / 2;
-                        let dst_y = (h as i32 - q.height as i32) / 2;
-                        let _ = canvas.copy(&tex, None, Rect::new(dst_x, dst_y, q.width, q.height));
+
+                // None = keep open, Some(None) = cancel, Some(Some(i)) = launch with choice i
+                let mut choice: Option<Option<usize>> = None;
+                for event in menu_events.drain(..) {
+                    match event {
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Up),
+                            ..
+                        }
+                        | Event::ControllerButtonDown {
+                            button: CButton::DPadUp,
+                            ..
+                        } => {
+                            *esel = esel.saturating_sub(1);
+                        }
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Down),
+                            ..
+                        }
+                        | Event::ControllerButtonDown {
+                            button: CButton::DPadDown,
+                            ..
+                        } => {
+                            *esel = (*esel + 1).min(choices.len() - 1);
+                        }
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Return),
+                            ..
+                        }
+                        | Event::ControllerButtonDown {
+                            button: CButton::A, ..
+                        }
+                        | Event::JoyButtonDown { button_idx: 0, .. } => {
+                            choice = Some(Some(*esel));
+                        }
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Escape),
+                            ..
+                        }
+                        | Event::ControllerButtonDown {
+                            button: CButton::B, ..
+                        } => {
+                            choice = Some(None);
+                        }
+                        Event::Quit { .. } => break 'running,
+                        _ => {}
+                    }
+                }
+                match choice {
+                    Some(Some(i)) => {
+                        let mut next = MenuState::Closed;
+                        if let Some(t) = choices.get(i).cloned() {
+                            if emu::program_missing(&t) {
+                                error_overlay =
+                                    Some((emu::not_found_message(&t.program), Instant::now()));
+                            } else if config.confirm_launch.unwrap_or(false) {
+                                next = launch_confirm(t, rom.clone());
+                            } else {
+                                pending_launch = Some((t, rom.clone()));
+                            }
+                        }
+                        menu_state = next;
                     }
+                    Some(None) => menu_state = MenuState::Closed,
+                    None => {}
                 }
-            } else {
-                error_overlay = None;
             }
-        }
-
-        // present moved after menu rendering so overlays are composed before presenting
-
-        // handle menu input and rendering after presenting main content
-        // menu state handling
-        let mut menu_next_state: Option<MenuState> = None;
-        match &mut menu_state {
-            MenuState::Closed => {}
-            MenuState::Open {
-                items,
-                selected: msel,
-            } => {
-                //println!(
-                //    "Rendering menu overlay, items={} selected={}",
-                //    items.len(),
-                //    msel
-                //);
-                // draw an opaque full-screen overlay so the menu is unmistakable
-                canvas.set_draw_color(menu_bg_c);
+            MenuState::Detail { rom, title, lines } => {
+                canvas.set_draw_color(overlay_rgba);
                 let _ = canvas.fill_rect(Rect::new(0, 0, w as u32, h as u32));
 
-                // menu box
-                let box_w = (w as i32) / 2;
-                let box_h = (items.len() as i32) * 28 + 40;
-                let box_x = (w as i32 - box_w) / 2;
-                let box_y = (h as i32 - box_h) / 2;
+                let line_h = font.height().max(1);
+                let box_w = w * 2 / 3;
+                let box_h = (lines.len() as i32) * line_h + 56;
+                let box_x = (w - box_w) / 2;
+                let box_y = (h - box_h) / 2;
                 canvas.set_draw_color(menu_box_c);
                 let _ = canvas.fill_rect(Rect::new(box_x, box_y, box_w as u32, box_h as u32));
 
-                // Big MENU label
-                if let Ok(surf_big) = font.render("MENU").blended(menu_text_c) {
-                    if let Ok(tex_big) = texture_creator.create_texture_from_surface(&surf_big) {
-                        let qb = tex_big.query();
-                        let bx = box_x + 12;
-                        let by = box_y + 8;
-                        let _ = canvas.copy(&tex_big, None, Rect::new(bx, by, qb.width, qb.height));
-                    }
-                }
-
-                // title
-                if let Ok(surf) = font.render("Settings").blended(menu_title_c) {
+                if let Ok(surf) = font.render(title).blended(menu_title_c) {
                     if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
                         let q = tex.query();
                         let _ = canvas.copy(
@@ -1546,22 +5119,12 @@ fn main() -> Result<(), String> {
                     }
                 }
 
-                // render items
-                for (i, it) in items.iter().enumerate() {
-                    let y = box_y + 40 + (i as i32) * 28;
-                    if i == *msel {
-                        canvas.set_draw_color(menu_selected_c);
-                        let _ =
-                            canvas.fill_rect(Rect::new(box_x + 8, y - 4, (box_w - 16) as u32, 28));
+                for (i, line) in lines.iter().enumerate() {
+                    if line.is_empty() {
+                        continue;
                     }
-                    let label = if it == "Toggle show_empty_systems" {
-                        let val = config.show_empty_systems.unwrap_or(false);
-                        format!("{}: {}", it, if val { "ON" } else { "OFF" })
-                    } else {
-                        it.clone()
-                    };
-
-                    if let Ok(surf) = font.render(&label).blended(menu_text_c) {
+                    let y = box_y + 44 + (i as i32) * line_h;
+                    if let Ok(surf) = font.render(line).blended(menu_text_c) {
                         if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
                             let q = tex.query();
                             let _ = canvas.copy(
@@ -1573,310 +5136,194 @@ fn main() -> Result<(), String> {
                     }
                 }
 
-                // menu overlay will be presented once per frame at the end of the render pass
-
-                // process input for menu using the events collected earlier this frame
+                // None = keep open, Some(false) = close, Some(true) = launch this ROM
+                let mut answer: Option<bool> = None;
                 for event in menu_events.drain(..) {
                     match event {
                         Event::KeyDown {
-                            keycode: Some(k), ..
-                        } => match k {
-                            Keycode::Up => {
-                                if *msel > 0 {
-                                    *msel -= 1;
-                                }
-                            }
-                            Keycode::Down => {
-                                if *msel + 1 < items.len() {
-                                    *msel += 1;
-                                }
-                            }
-                            Keycode::Return => {
-                                let sel_label = items[*msel].as_str();
-                                match sel_label {
-                                    "Toggle show_empty_systems" => {
-                                        let cur = config.show_empty_systems.unwrap_or(false);
-                                        config.show_empty_systems = Some(!cur);
-                                        menu_message = Some((
-                                            format!("show_empty_systems set to {}", !cur),
-                                            Instant::now(),
-                                        ));
-                                    }
-                                    "Remap controls" => {
-                                        // enter remap state
-                                        let actions = vec![
-                                            "A".to_string(),
-                                            "B".to_string(),
-                                            "UP".to_string(),
-                                            "DOWN".to_string(),
-                                            "LEFT".to_string(),
-                                            "RIGHT".to_string(),
-                                            "START".to_string(),
-                                        ];
-                                        let remap = MenuState::Remap {
-                                            actions,
-                                            idx: 0,
-                                            temp_map: HashMap::new(),
-                                        };
-                                        menu_next_state = Some(remap);
-                                        break;
-                                    }
-                                    "Reload config" => {
-                                        // reload config from disk and re-scan roms
-                                        let prev_system =
-                                            systems_vec.get(current_system_idx).cloned();
-                                        config = load_config();
-                                        groups = scan_grouped(Path::new(&roms_dir), &config);
-
-                                        // rebuild systems_vec
-                                        systems_vec.clear();
-                                        if let Some(systems) = config.systems.as_ref() {
-                                            for k in systems.keys() {
-                                                let k_l = k.to_lowercase();
-                                                let has_entries = groups
-                                                    .get(&k_l)
-                                                    .map(|v| !v.is_empty())
-                                                    .unwrap_or(false);
-                                                if has_entries
-                                                    || config.show_empty_systems.unwrap_or(false)
-                                                {
-                                                    systems_vec.push(k_l);
-                                                }
-                                            }
-                                        }
-
-                                        // restore current_system_idx if possible
-                                        if let Some(prev) = prev_system {
-                                            if let Some(pos) =
-                                                systems_vec.iter().position(|s| s == &prev)
-                                            {
-                                                current_system_idx = pos;
-                                            } else {
-                                                current_system_idx = 0;
-                                            }
-                                        } else {
-                                            current_system_idx = 0;
-                                        }
-
-                                        // update current roms and textures
-                                        let cur = systems_vec.get(current_system_idx).cloned();
-                                        current_roms = cur
-                                            .as_ref()
-                                            .and_then(|s| groups.get(s).cloned())
-                                            .unwrap_or_default();
-                                        selected = 0;
-                                        scroll_offset = 0;
-                                        text_textures.clear();
-                                        for _ in 0..current_roms.len() {
-                                            text_textures.push(None);
-                                        }
-
-                                        menu_message =
-                                            Some(("Config reloaded".to_string(), Instant::now()));
-                                    }
-                                    "Save config" => {
-                                        if let Err(e) = write_config(&config) {
-                                            menu_message = Some((
-                                                format!("Save failed: {}", e),
-                                                Instant::now(),
-                                            ));
-                                        } else {
-                                            menu_message =
-                                                Some(("Config saved".to_string(), Instant::now()));
-                                        }
-                                    }
-                                    "Close" => {
-                                        menu_next_state = Some(MenuState::Closed);
-                                    }
-                                    "Exit" => {
-                                        should_quit = true;
-                                        menu_next_state = Some(MenuState::Closed);
-                                    }
-                                    _ => {}
-                                }
-                            }
-                            Keycode::Escape => {
-                                menu_next_state = Some(MenuState::Closed);
-                            }
-                            _ => {}
-                        },
-                        Event::ControllerButtonDown { button, .. } => {
-                            match button {
-                                CButton::DPadUp => {
-                                    if *msel > 0 {
-                                        *msel -= 1;
-                                    }
-                                }
-                                CButton::DPadDown => {
-                                    if *msel + 1 < items.len() {
-                                        *msel += 1;
-                                    }
-                                }
-                                CButton::A => {
-                                    let sel_label = items[*msel].as_str();
-                                    match sel_label {
-                                        "Toggle show_empty_systems" => {
-                                            let cur = config.show_empty_systems.unwrap_or(false);
-                                            config.show_empty_systems = Some(!cur);
-                                            menu_message = Some((
-                                                format!("show_empty_systems set to {}", !cur),
-                                                Instant::now(),
-                                            ));
-                                        }
-                                        "Remap controls" => {
-                                            let actions = vec![
-                                                "A".to_string(),
-                                                "B".to_string(),
-                                                "UP".to_string(),
-                                                "DOWN".to_string(),
-                                                "LEFT".to_string(),
-                                                "RIGHT".to_string(),
-                                                "START".to_string(),
-                                            ];
-                                            let remap = MenuState::Remap {
-                                                actions,
-                                                idx: 0,
-                                                temp_map: HashMap::new(),
-                                            };
-                                            menu_next_state = Some(remap);
-                                            break;
-                                        }
-                                        "Reload config" => {
-                                            menu_message = Some(("Reload not implemented in-menu; restart app to apply".to_string(), Instant::now()));
-                                        }
-                                        "Save config" => {
-                                            if let Err(e) = write_config(&config) {
-                                                menu_message = Some((
-                                                    format!("Save failed: {}", e),
-                                                    Instant::now(),
-                                                ));
-                                            } else {
-                                                menu_message = Some((
-                                                    "Config saved".to_string(),
-                                                    Instant::now(),
-                                                ));
-                                            }
-                                        }
-                                        "Close" => {
-                                            menu_next_state = Some(MenuState::Closed);
-                                        }
-                                        "Exit" => {
-                                            should_quit = true;
-                                            menu_next_state = Some(MenuState::Closed);
-                                        }
-                                        _ => {}
-                                    }
-                                }
-                                CButton::B => {
-                                    menu_next_state = Some(MenuState::Closed);
-                                }
-                                _ => {}
-                            }
+                            keycode: Some(Keycode::Return),
+                            ..
                         }
-                        Event::JoyButtonDown { button_idx, .. } => {
-                            // treat as pressing A when in menu to select
-                            if *msel < items.len() {
-                                // map button to selection
-                                if button_idx == 0 {
-                                    // common: A
-                                    let sel_label = items[*msel].as_str();
-                                    if sel_label == "Remap controls" {
-                                        let actions = vec![
-                                            "A".to_string(),
-                                            "B".to_string(),
-                                            "UP".to_string(),
-                                            "DOWN".to_string(),
-                                            "LEFT".to_string(),
-                                            "RIGHT".to_string(),
-                                            "START".to_string(),
-                                        ];
-                                        menu_next_state = Some(MenuState::Remap {
-                                            actions,
-                                            idx: 0,
-                                            temp_map: HashMap::new(),
-                                        });
-                                        break;
-                                    } else if sel_label == "Exit" {
-                                        should_quit = true;
-                                        menu_next_state = Some(MenuState::Closed);
-                                        break;
-                                    }
-                                }
-                            }
+                        | Event::ControllerButtonDown {
+                            button: CButton::A, ..
+                        }
+                        | Event::JoyButtonDown { button_idx: 0, .. } => {
+                            answer = Some(true);
+                        }
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Escape | Keycode::I),
+                            ..
+                        }
+                        | Event::ControllerButtonDown {
+                            button: CButton::B | CButton::X,
+                            ..
+                        } => {
+                            answer = Some(false);
                         }
+                        Event::Quit { .. } => break 'running,
                         _ => {}
                     }
                 }
-                // apply any pending menu state change
-                if let Some(s) = menu_next_state {
-                    menu_state = s;
-                }
-                // If an Exit was chosen in the menu, break out of main loop
-                if should_quit {
-                    break 'running;
+                match answer {
+                    Some(true) => {
+                        launch_request = Some(rom.clone());
+                        menu_state = MenuState::Closed;
+                    }
+                    Some(false) => menu_state = MenuState::Closed,
+                    None => {}
                 }
             }
-            MenuState::Remap {
-                actions,
-                idx,
-                temp_map,
+            MenuState::Report {
+                title,
+                lines,
+                scroll,
             } => {
-                // draw remap overlay
                 canvas.set_draw_color(overlay_rgba);
                 let _ = canvas.fill_rect(Rect::new(0, 0, w as u32, h as u32));
-                let prompt = format!(
-                    "Press a button for: {}",
-                    actions.get(*idx).unwrap_or(&"".to_string())
-                );
-                if let Ok(surf) = font.render(&prompt).blended(text_primary_c) {
+
+                let line_h = font.height().max(1);
+                let box_w = w * 3 / 4;
+                let box_h = h - 80;
+                let box_x = (w - box_w) / 2;
+                let box_y = 40;
+                canvas.set_draw_color(menu_box_c);
+                let _ = canvas.fill_rect(Rect::new(box_x, box_y, box_w as u32, box_h as u32));
+
+                let page = ((box_h - 56) / line_h).max(1) as usize;
+                let max_scroll = lines.len().saturating_sub(page);
+                let heading = if lines.len() > page {
+                    format!(
+                        "{}  ({}-{} of {})",
+                        title,
+                        *scroll + 1,
+                        (*scroll + page).min(lines.len()),
+                        lines.len()
+                    )
+                } else {
+                    title.clone()
+                };
+                if let Ok(surf) = font.render(&heading).blended(menu_title_c) {
                     if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
                         let q = tex.query();
-                        let dst_x = ((w as i32) - q.width as i32) / 2;
-                        let dst_y = (h as i32) / 2;
-                        let _ = canvas.copy(&tex, None, Rect::new(dst_x, dst_y, q.width, q.height));
+                        let _ = canvas.copy(
+                            &tex,
+                            None,
+                            Rect::new(box_x + 12, box_y + 8, q.width, q.height),
+                        );
                     }
                 }
-                canvas.present();
 
-                // capture one event for remapping
-                if let Some(evt) = event_pump.wait_event_timeout(3000) {
-                    match evt {
-                        Event::ControllerButtonDown { button, .. } => {
-                            let key = format!("controller:{:?}", button);
-                            if let Some(act) = actions.get(*idx).cloned() {
-                                temp_map.insert(act, key);
-                                *idx += 1;
-                            }
+                for (i, line) in lines.iter().skip(*scroll).take(page).enumerate() {
+                    if line.is_empty() {
+                        continue;
+                    }
+                    let y = box_y + 44 + (i as i32) * line_h;
+                    if let Ok(surf) = font.render(line).blended(menu_text_c) {
+                        if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
+                            // long paths are cut off at the box edge
+                            let q = tex.query();
+                            let cw = q.width.min((box_w - 32) as u32);
+                            let _ = canvas.copy(
+                                &tex,
+                                Rect::new(0, 0, cw, q.height),
+                                Rect::new(box_x + 16, y, cw, q.height),
+                            );
                         }
-                        Event::JoyButtonDown { button_idx, .. } => {
-                            let key = format!("joybutton:{}", button_idx);
-                            if let Some(act) = actions.get(*idx).cloned() {
-                                temp_map.insert(act, key);
-                                *idx += 1;
-                            }
+                    }
+                }
+
+                // up/down scroll a line, page up/down or the shoulder buttons a screen
+                let mut close = false;
+                for event in menu_events.drain(..) {
+                    match event {
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Up),
+                            ..
+                        }
+                        | Event::ControllerButtonDown {
+                            button: CButton::DPadUp,
+                            ..
+                        } => *scroll = scroll.saturating_sub(1),
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Down),
+                            ..
+                        }
+                        | Event::ControllerButtonDown {
+                            button: CButton::DPadDown,
+                            ..
+                        } => *scroll = (*scroll + 1).min(max_scroll),
+                        Event::KeyDown {
+                            keycode: Some(Keycode::PageUp),
+                            ..
+                        }
+                        | Event::ControllerButtonDown {
+                            button: CButton::LeftShoulder,
+                            ..
+                        } => *scroll = scroll.saturating_sub(page),
+                        Event::KeyDown {
+                            keycode: Some(Keycode::PageDown),
+                            ..
+                        }
+                        | Event::ControllerButtonDown {
+                            button: CButton::RightShoulder,
+                            ..
+                        } => *scroll = (*scroll + page).min(max_scroll),
+                        Event::KeyDown {
+                            keycode: Some(Keycode::Escape | Keycode::Return | Keycode::F1),
+                            ..
                         }
+                        | Event::ControllerButtonDown {
+                            button: CButton::A | CButton::B,
+                            ..
+                        } => close = true,
+                        Event::Quit { .. } => break 'running,
                         _ => {}
                     }
                 }
+                if close {
+                    menu_state = MenuState::Closed;
+                }
+            }
+        }
 
-                // finish
-                if *idx >= actions.len() {
-                    // commit to config
-                    config.controller_map = Some(temp_map.clone());
-                    if let Err(e) = write_config(&config) {
-                        menu_message = Some((format!("Save failed: {}", e), Instant::now()));
-                    } else {
-                        menu_message =
-                            Some(("Controller mapping saved".to_string(), Instant::now()));
+        // volume bar, centered near the bottom
+        if let Some((pct, when)) = volume_osd {
+            if when.elapsed().as_secs() < VOLUME_OSD_SECS {
+                let bar_w = w / 3;
+                let bar_x = (w - bar_w) / 2;
+                let bar_y = h - 120;
+                canvas.set_draw_color(overlay_rgba);
+                let _ = canvas.fill_rect(Rect::new(
+                    bar_x - 12,
+                    bar_y - font.height() - 16,
+                    (bar_w + 24) as u32,
+                    (font.height() + 44) as u32,
+                ));
+                if let Ok(surf) = font
+                    .render(&format!("Volume {}%", pct))
+                    .blended(text_primary_c)
+                {
+                    if let Ok(tex) = texture_creator.create_texture_from_surface(&surf) {
+                        let q = tex.query();
+                        let dst = Rect::new(bar_x, bar_y - font.height() - 8, q.width, q.height);
+                        let _ = canvas.copy(&tex, None, dst);
                     }
-                    menu_state = MenuState::Closed;
                 }
+                canvas.set_draw_color(scrollbar_track_c);
+                let _ = canvas.fill_rect(Rect::new(bar_x, bar_y, bar_w as u32, 16));
+                let filled = bar_w * pct.min(100) as i32 / 100;
+                if filled > 0 {
+                    canvas.set_draw_color(tile_selected_c);
+                    let _ = canvas.fill_rect(Rect::new(bar_x, bar_y, filled as u32, 16));
+                }
+            } else {
+                volume_osd = None;
             }
         }
 
-        // render menu message overlay if present (auto-hide after 3s)
+        // render menu message overlay if present (auto-hide after message_overlay_secs)
         if let Some((ref msg, when)) = menu_message {
-            if when.elapsed().as_secs() < 3 {
+            if when.elapsed().as_secs() < config.message_overlay_secs.unwrap_or(3) {
                 canvas.set_draw_color(message_overlay_rgba);
                 let _ = canvas.fill_rect(Rect::new(0, (h as i32) - 60, w as u32, 60));
                 if let Ok(surf) = font.render(msg).blended(text_primary_c) {
@@ -1894,9 +5341,396 @@ fn main() -> Result<(), String> {
         // present final composition (main UI + possible menu overlay)
         canvas.present();
 
+        if let (Some(mc), Some(mtc)) = (marquee_canvas.as_mut(), marquee_texture_creator.as_ref()) {
+            let system = systems_vec
+                .get(current_system_idx)
+                .cloned()
+                .unwrap_or_default();
+            let rom = current_roms
+                .get(selected)
+                .filter(|_| !at_system_list)
+                .cloned();
+            let selection = (system, rom);
+            if marquee_shown.as_ref() != Some(&selection) {
+                marquee_art = marquee_art_dir
+                    .as_deref()
+                    .and_then(|d| marquee::art_path(d, &selection.0, selection.1.as_deref()))
+                    .and_then(|p| marquee::load_art(mtc, &p));
+                marquee_shown = Some(selection);
+            }
+            let mut labels = Vec::new();
+            if marquee_art.is_none() {
+                if let Some((system, rom)) = &marquee_shown {
+                    let system_name = system_display_name(&config, system);
+                    if let Some(t) =
+                        marquee_system_text.get(&font, mtc, &system_name, text_primary_c)
+                    {
+                        labels.push(t);
+                    }
+                    let rom_name = rom
+                        .as_ref()
+                        .and_then(|r| rom_label(&config, Path::new(&roms_dir), r))
+                        .unwrap_or_default();
+                    if let Some(t) = marquee_rom_text.get(&font, mtc, &rom_name, text_primary_c) {
+                        labels.push(t);
+                    }
+                }
+            }
+            marquee::draw(mc, marquee_art.as_ref(), &labels);
+        }
+
         // small delay
-        std::thread::sleep(std::time::Duration::from_millis(16));
+        std::thread::sleep(Duration::from_millis(16));
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn v0_config_is_migrated_without_losing_settings() {
+        let v0 = r#"
+show_empty_systems = true
+default_roms_path = "/home/pi/roms"
+default = { program = "mgba-qt", args = ["{rom}"] }
+
+[systems]
+snes = { program = "snes9x", args = ["{rom}"], display_name = "Super Nintendo" }
+"#;
+        let mut value: toml::Value = toml::from_str(v0).unwrap();
+        // v0 -> v1 only adds the version, which isn't worth rewriting the file for
+        assert!(!migrate_config(&mut value));
+
+        // the rewritten file must load back to the same settings
+        let rewritten = toml::to_string_pretty(&value).unwrap();
+        let cfg: ConfigFile = toml::from_str(&rewritten).unwrap();
+        assert_eq!(cfg.version, Some(CONFIG_VERSION));
+        assert_eq!(cfg.show_empty_systems, Some(true));
+        assert_eq!(cfg.default_roms_path.as_deref(), Some("/home/pi/roms"));
+        assert_eq!(cfg.default.unwrap().program, "mgba-qt");
+        let snes = &cfg.systems.unwrap()["snes"];
+        assert_eq!(snes.program, "snes9x");
+        assert_eq!(snes.args, vec!["{rom}".to_string()]);
+        assert_eq!(snes.display_name.as_deref(), Some("Super Nintendo"));
+    }
+
+    // A dir in the temp dir holding a roms dir with a small file at each of `files` (relative to
+    // it), removed again when dropped. Tests put config files next to the roms dir.
+    struct RomsFixture {
+        dir: PathBuf,
+    }
+
+    impl RomsFixture {
+        fn new(name: &str, files: &[&str]) -> RomsFixture {
+            let dir =
+                std::env::temp_dir().join(format!("rpi_frontend_{}_{}", name, std::process::id()));
+            std::fs::create_dir_all(dir.join("roms")).unwrap();
+            for f in files {
+                let p = dir.join("roms").join(f);
+                std::fs::create_dir_all(p.parent().unwrap()).unwrap();
+                std::fs::write(&p, b"rom").unwrap();
+            }
+            RomsFixture { dir }
+        }
+
+        fn roms(&self) -> PathBuf {
+            self.dir.join("roms")
+        }
+    }
+
+    impl Drop for RomsFixture {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.dir);
+        }
+    }
+
+    #[test]
+    fn loading_a_v0_config_leaves_the_file_alone() {
+        let fixture = RomsFixture::new("migrate", &[]);
+        let path = fixture.dir.join("config.toml");
+        let bak = fixture.dir.join("config.toml.bak");
+
+        let v0 = "# my snes setup\nshow_empty_systems = true\n";
+        std::fs::write(&path, v0).unwrap();
+        let loaded = load_config(Some(&path));
+        assert!(loaded.parse_error.is_none());
+        assert_eq!(loaded.value.version, Some(CONFIG_VERSION));
+        assert_eq!(loaded.value.show_empty_systems, Some(true));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), v0);
+        assert!(!bak.exists());
+
+        // a config that doesn't load isn't touched either
+        let broken = "# typo below\nshow_empty_systems = \"yes\"\n";
+        std::fs::write(&path, broken).unwrap();
+        assert!(load_config(Some(&path)).parse_error.is_some());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), broken);
+        assert!(!bak.exists());
+    }
+
+    #[test]
+    fn current_config_is_not_migrated() {
+        let mut value: toml::Value =
+            toml::from_str(&format!("version = {}\n", CONFIG_VERSION)).unwrap();
+        assert!(!migrate_config(&mut value));
+    }
+
+    #[test]
+    fn mixed_case_system_keys_resolve() {
+        let fixture = RomsFixture::new("keys", &["Snes/Zelda.sfc", "gba/Metroid.gba"]);
+        let roms = fixture.roms();
+        let cfg_path = fixture.dir.join("config.toml");
+        std::fs::write(
+            &cfg_path,
+            r#"
+version = 1
+
+[systems]
+SNES = { program = "snes9x", args = ["{rom}"], display_name = "Super Nintendo" }
+Gba = { program = "mgba-qt", args = ["{rom}"] }
+"#,
+        )
+        .unwrap();
+
+        let cfg = load_config(Some(&cfg_path)).value;
+        let mut keys: Vec<&String> = cfg.systems.as_ref().unwrap().keys().collect();
+        keys.sort();
+        assert_eq!(keys, ["gba", "snes"]);
+        assert_eq!(system_display_name(&cfg, "snes"), "Super Nintendo");
+
+        let (groups, _, _) = scan_grouped(&roms, &cfg);
+        assert_eq!(groups["snes"], vec![roms.join("Snes").join("Zelda.sfc")]);
+        assert_eq!(groups["gba"], vec![roms.join("gba").join("Metroid.gba")]);
+    }
+
+    #[test]
+    fn system_visible_extensions_override_the_global_list() {
+        let fixture = RomsFixture::new(
+            "visible",
+            &[
+                "gba/Metroid.gba",
+                "gba/Metroid.sav",
+                "gba/Tetris.gb",
+                "gb/Tetris.gb",
+                "gb/notes.txt",
+            ],
+        );
+        let roms = fixture.roms();
+        let systems = r#"
+[systems]
+gba = { program = "mgba-qt", args = ["{rom}"], visible_extensions = ["gba"] }
+gb = { program = "mgba-qt", args = ["{rom}"] }
+"#;
+        let names = |groups: &HashMap<String, Vec<PathBuf>>, sys: &str| -> Vec<String> {
+            groups[sys]
+                .iter()
+                .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+                .collect()
+        };
+
+        // the system's own list wins, the global one fills in for systems without one
+        let cfg: ConfigFile =
+            toml::from_str(&format!("visible_extensions = [\"gb\"]\n{}", systems)).unwrap();
+        let (groups, _, _) = scan_grouped(&roms, &cfg);
+        assert_eq!(names(&groups, "gba"), ["Metroid.gba"]);
+        assert_eq!(names(&groups, "gb"), ["Tetris.gb"]);
+
+        // without a global list, systems without their own show everything
+        let cfg: ConfigFile = toml::from_str(systems).unwrap();
+        let (groups, _, _) = scan_grouped(&roms, &cfg);
+        assert_eq!(names(&groups, "gba"), ["Metroid.gba"]);
+        assert_eq!(names(&groups, "gb"), ["notes.txt", "Tetris.gb"]);
+    }
+
+    #[test]
+    fn rom_system_skips_unconfigured_ignored_and_invisible_files() {
+        let cfg: ConfigFile = toml::from_str(
+            r#"
+[systems]
+snes = { program = "snes9x", args = ["{rom}"] }
+gba = { program = "mgba-qt", args = ["{rom}"], visible_extensions = ["gba"] }
+psx = { program = "pcsx", args = ["{rom}"], ignored_extensions = ["sav"] }
+"#,
+        )
+        .unwrap();
+        let root = Path::new("/roms");
+        let order = configured_systems_ordered(&cfg);
+        let system = |p: &str| rom_system(Path::new(p), root, &cfg, &order);
+
+        assert_eq!(system("/roms/snes/Zelda.sfc").as_deref(), Some("snes"));
+        assert_eq!(system("/roms/SNES/sub/Zelda.sfc").as_deref(), Some("snes"));
+        // archives are ignored by default
+        assert_eq!(system("/roms/snes/Zelda.zip"), None);
+        assert_eq!(system("/roms/gba/Metroid.GBA").as_deref(), Some("gba"));
+        assert_eq!(system("/roms/gba/Metroid.sav"), None);
+        // a system's own ignored_extensions replace the default list
+        assert_eq!(system("/roms/psx/Game.zip").as_deref(), Some("psx"));
+        assert_eq!(system("/roms/psx/Game.sav"), None);
+        // unconfigured folders and loose files in the root are not listed
+        assert_eq!(system("/roms/n64/Mario.z64"), None);
+        assert_eq!(system("/roms/Zelda.sfc"), None);
+    }
+
+    #[test]
+    fn rom_system_groups_by_extension() {
+        let cfg: ConfigFile = toml::from_str(
+            r#"
+group_by = "extension"
+system_order = ["gbc", "gb"]
+
+[systems]
+gb = { program = "mgba-qt", args = ["{rom}"], extensions = ["gb"] }
+gbc = { program = "mgba-qt", args = ["{rom}"], extensions = ["gbc", "gb"] }
+"#,
+        )
+        .unwrap();
+        let root = Path::new("/roms");
+        let order = configured_systems_ordered(&cfg);
+        let system = |p: &str| rom_system(Path::new(p), root, &cfg, &order);
+
+        // folders don't matter; the first system in display order claiming the extension wins
+        assert_eq!(system("/roms/misc/Tetris.gb").as_deref(), Some("gbc"));
+        assert_eq!(system("/roms/Pokemon.GBC").as_deref(), Some("gbc"));
+        assert_eq!(system("/roms/readme.txt"), None);
+        assert_eq!(system("/roms/noextension"), None);
+    }
+
+    #[test]
+    fn scan_filters_and_sorts_the_roms_tree() {
+        let fixture = RomsFixture::new(
+            "scan",
+            &[
+                "snes/Game 10.sfc",
+                "snes/Game 2.sfc",
+                "snes/Game 1.zip",
+                "snes/.Game 3.sfc",
+                "snes/Thumbs.db",
+                "snes/sub/Game 5.sfc",
+                "snes/sub/deep/Game 4.sfc",
+                "n64/Mario.z64",
+            ],
+        );
+        let roms = fixture.roms();
+        std::fs::write(roms.join("snes/Empty.sfc"), b"").unwrap();
+        let systems = r#"
+[systems]
+snes = { program = "snes9x", args = ["{rom}"] }
+"#;
+        let names = |groups: &HashMap<String, Vec<PathBuf>>| -> Vec<String> {
+            groups["snes"]
+                .iter()
+                .map(|p| {
+                    p.strip_prefix(&roms)
+                        .unwrap()
+                        .to_string_lossy()
+                        .into_owned()
+                })
+                .collect()
+        };
+
+        let cfg: ConfigFile = toml::from_str(&format!(
+            "max_scan_depth = 1
+min_rom_size = 1
+{}",
+            systems
+        ))
+        .unwrap();
+        let (groups, _, errors) = scan_grouped(&roms, &cfg);
+        assert!(errors.is_empty());
+        assert_eq!(groups.len(), 1);
+        assert_eq!(
+            names(&groups),
+            ["snes/Game 2.sfc", "snes/Game 10.sfc", "snes/sub/Game 5.sfc"]
+        );
+
+        // defaults: no depth or size limit; hidden and junk files are still skipped
+        let cfg: ConfigFile = toml::from_str(systems).unwrap();
+        let (groups, _, _) = scan_grouped(&roms, &cfg);
+        assert_eq!(
+            names(&groups),
+            [
+                "snes/Empty.sfc",
+                "snes/Game 2.sfc",
+                "snes/Game 10.sfc",
+                "snes/sub/deep/Game 4.sfc",
+                "snes/sub/Game 5.sfc",
+            ]
+        );
+
+        let cfg: ConfigFile = toml::from_str(&format!(
+            "ignore_hidden = false
+{}",
+            systems
+        ))
+        .unwrap();
+        let (groups, _, _) = scan_grouped(&roms, &cfg);
+        assert!(names(&groups).contains(&"snes/.Game 3.sfc".to_string()));
+        assert!(names(&groups).contains(&"snes/Thumbs.db".to_string()));
+    }
+
+    #[test]
+    fn colliding_system_keys_keep_the_lowercase_one() {
+        let tmpl = |program: &str| -> CmdTemplate {
+            toml::from_str(&format!("program = {:?}\nargs = [\"{{rom}}\"]", program)).unwrap()
+        };
+        let systems = HashMap::from([
+            ("SNES".to_string(), tmpl("upper")),
+            ("snes".to_string(), tmpl("lower")),
+            ("Snes".to_string(), tmpl("mixed")),
+            ("PSX".to_string(), tmpl("psx")),
+        ]);
+        let out = normalize_system_keys(systems);
+        assert_eq!(out.len(), 2);
+        assert_eq!(out["snes"].program, "lower");
+        assert_eq!(out["psx"].program, "psx");
+    }
+
+    #[test]
+    fn reset_config_keeps_earlier_backups() {
+        let fixture = RomsFixture::new("reset", &[]);
+        let dir = &fixture.dir;
+        let path = dir.join("config.toml");
+        fn write_default(p: &Path) -> std::io::Result<()> {
+            std::fs::write(p, "default")
+        }
+        std::fs::write(&path, "first").unwrap();
+        reset_to_default(&path, write_default).unwrap();
+        std::fs::write(&path, "second").unwrap();
+        reset_to_default(&path, write_default).unwrap();
+        let read = |name: &str| std::fs::read_to_string(dir.join(name)).unwrap();
+        assert_eq!(read("config.toml.bak"), "first");
+        assert_eq!(read("config.toml.bak.1"), "second");
+        assert_eq!(read("config.toml"), "default");
+    }
+
+    #[test]
+    fn system_navigation_is_a_no_op_without_systems() {
+        // regression: Right/DPadRight used `% systems_vec.len()` and panicked on an empty roms dir
+        assert_eq!(next_system_idx(0, 0), None);
+        assert_eq!(prev_system_idx(0, 0), None);
+    }
+
+    #[test]
+    fn system_navigation_wraps() {
+        assert_eq!(next_system_idx(0, 3), Some(1));
+        assert_eq!(next_system_idx(2, 3), Some(0));
+        assert_eq!(prev_system_idx(0, 3), Some(2));
+        assert_eq!(prev_system_idx(2, 3), Some(1));
+        assert_eq!(next_system_idx(0, 1), Some(0));
+    }
+
+    #[test]
+    fn move_selection_handles_empty_list() {
+        assert_eq!(move_selection(0, 0, 0, 5, 1), (0, 0));
+        assert_eq!(move_selection(0, 0, 0, 5, -10), (0, 0));
+    }
+
+    #[test]
+    fn move_selection_clamps_and_scrolls() {
+        assert_eq!(move_selection(0, 0, 20, 5, 5), (5, 1));
+        assert_eq!(move_selection(18, 15, 20, 5, 5), (19, 15));
+        assert_eq!(move_selection(3, 2, 20, 5, -5), (0, 0));
+    }
+}
@@ -0,0 +1,144 @@
+// Optional second window for cabinets with a small extra screen (marquee_display). It fills that
+// screen with art for the selected ROM, falling back to the system's art and then to their names.
+// Art is BMP, which SDL loads without extra libraries, and lives in the art dir as
+//   <system>/<ROM name without extension>.bmp   for a ROM
+//   <system>.bmp                                for a system
+use sdl2::pixels::Color;
+use sdl2::rect::Rect;
+use sdl2::render::{Texture, TextureCreator, WindowCanvas};
+use sdl2::video::WindowContext;
+use std::path::{Path, PathBuf};
+
+// A borderless window covering `display`, or None (with a warning) if it can't be opened.
+pub fn open(video: &sdl2::VideoSubsystem, display: i32) -> Option<WindowCanvas> {
+    let bounds = match video.display_bounds(display) {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("marquee_display {} not available: {}", display, e);
+            return None;
+        }
+    };
+    let canvas = video
+        .window("RPI Frontend marquee", bounds.width(), bounds.height())
+        .position(bounds.x(), bounds.y())
+        .borderless()
+        .build()
+        .map_err(|e| e.to_string())
+        .and_then(|w| {
+            w.into_canvas()
+                .accelerated()
+                .build()
+                .map_err(|e| e.to_string())
+        });
+    match canvas {
+        Ok(c) => Some(c),
+        Err(e) => {
+            eprintln!("Could not open the marquee window: {}", e);
+            None
+        }
+    }
+}
+
+// Art for `rom` of `system` (or for the system alone when there is no ROM), if any exists.
+pub fn art_path(art_dir: &Path, system: &str, rom: Option<&Path>) -> Option<PathBuf> {
+    let rom_art = rom.map(|r| {
+        let stem = r.file_stem().unwrap_or_default().to_string_lossy();
+        art_dir.join(system).join(format!("{}.bmp", stem))
+    });
+    rom_art
+        .into_iter()
+        .chain(std::iter::once(art_dir.join(format!("{}.bmp", system))))
+        .find(|p| p.is_file())
+}
+
+pub fn load_art<'a>(
+    texture_creator: &'a TextureCreator<WindowContext>,
+    path: &Path,
+) -> Option<Texture<'a>> {
+    let surf = match sdl2::surface::Surface::load_bmp(path) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Could not load {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    texture_creator.create_texture_from_surface(&surf).ok()
+}
+
+// The largest rect with the art's aspect ratio that fits a `w`x`h` screen, centered on it.
+fn fit_rect(art_w: u32, art_h: u32, w: u32, h: u32) -> Rect {
+    let (art_w, art_h) = (art_w.max(1) as u64, art_h.max(1) as u64);
+    let (mut dw, mut dh) = (w as u64, art_h * w as u64 / art_w);
+    if dh > h as u64 {
+        (dw, dh) = (art_w * h as u64 / art_h, h as u64);
+    }
+    Rect::new(
+        (w as i32 - dw as i32) / 2,
+        (h as i32 - dh as i32) / 2,
+        dw as u32,
+        dh as u32,
+    )
+}
+
+// Redraw the window: `art` scaled to fit, or else the `labels` stacked in the middle.
+pub fn draw(canvas: &mut WindowCanvas, art: Option<&Texture>, labels: &[&Texture]) {
+    canvas.set_draw_color(Color::RGB(0, 0, 0));
+    canvas.clear();
+    let (w, h) = canvas.output_size().unwrap_or((0, 0));
+    if let Some(tex) = art {
+        let q = tex.query();
+        let _ = canvas.copy(tex, None, fit_rect(q.width, q.height, w, h));
+    } else {
+        let total: u32 = labels.iter().map(|t| t.query().height).sum();
+        let mut y = (h as i32 - total as i32) / 2;
+        for tex in labels {
+            let q = tex.query();
+            let x = (w as i32 - q.width as i32) / 2;
+            let _ = canvas.copy(tex, None, Rect::new(x, y, q.width, q.height));
+            y += q.height as i32;
+        }
+    }
+    canvas.present();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fit_rect_keeps_the_aspect_ratio() {
+        // wider than the screen: full width, centered vertically
+        assert_eq!(fit_rect(400, 100, 200, 200), Rect::new(0, 75, 200, 50));
+        // taller than the screen: full height, centered horizontally
+        assert_eq!(fit_rect(100, 400, 200, 200), Rect::new(75, 0, 50, 200));
+        // same ratio: the whole screen
+        assert_eq!(fit_rect(64, 32, 1280, 640), Rect::new(0, 0, 1280, 640));
+    }
+
+    #[test]
+    fn fit_rect_survives_empty_art() {
+        assert_eq!(fit_rect(0, 0, 200, 100), Rect::new(50, 0, 100, 100));
+    }
+
+    #[test]
+    fn art_path_prefers_rom_art_then_system_art() {
+        let dir = std::env::temp_dir().join(format!("rpi_frontend_marquee_{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("snes")).unwrap();
+        let rom = Path::new("/roms/snes/Zelda.sfc");
+        assert_eq!(art_path(&dir, "snes", Some(rom)), None);
+
+        std::fs::write(dir.join("snes.bmp"), "").unwrap();
+        assert_eq!(
+            art_path(&dir, "snes", Some(rom)),
+            Some(dir.join("snes.bmp"))
+        );
+        assert_eq!(art_path(&dir, "snes", None), Some(dir.join("snes.bmp")));
+
+        std::fs::write(dir.join("snes").join("Zelda.bmp"), "").unwrap();
+        assert_eq!(
+            art_path(&dir, "snes", Some(rom)),
+            Some(dir.join("snes").join("Zelda.bmp"))
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
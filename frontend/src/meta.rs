@@ -0,0 +1,41 @@
+// Optional per-ROM metadata: `Game.meta.toml` next to `Game.gba`. Only the scan notes which ROMs
+// have one; the file itself is read when the detail view opens.
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+const META_SUFFIX: &str = ".meta.toml";
+
+#[derive(Deserialize, Default, Clone, Debug, PartialEq)]
+pub struct RomMeta {
+    pub title: Option<String>,
+    pub players: Option<u32>,
+    pub genre: Option<String>,
+    pub description: Option<String>,
+}
+
+// Where the metadata for `rom` lives: same folder, ROM name without its extension.
+pub fn meta_path(rom: &Path) -> PathBuf {
+    let stem = rom.file_stem().unwrap_or_default().to_string_lossy();
+    rom.with_file_name(format!("{}{}", stem, META_SUFFIX))
+}
+
+// True for `*.meta.toml` files, which are never listed as ROMs.
+pub fn is_meta_file(p: &Path) -> bool {
+    p.file_name()
+        .and_then(|n| n.to_str())
+        .map(|n| n.to_lowercase().ends_with(META_SUFFIX))
+        .unwrap_or(false)
+}
+
+// Read the metadata for `rom`. A file that fails to parse is reported and treated as missing.
+pub fn load(rom: &Path) -> Option<RomMeta> {
+    let p = meta_path(rom);
+    let contents = std::fs::read_to_string(&p).ok()?;
+    match toml::from_str::<RomMeta>(&contents) {
+        Ok(m) => Some(m),
+        Err(e) => {
+            eprintln!("Failed to parse {}: {}", p.display(), e);
+            None
+        }
+    }
+}
@@ -0,0 +1,121 @@
+// Locations for files the frontend keeps between runs. Settings live in the config dir (see
+// user_config_path / style::user_style_path); state the user does not edit goes here.
+
+// `name` inside the app's data dir: $XDG_DATA_HOME/rpi_emulator_frontend, falling back to
+// ~/.local/share/rpi_emulator_frontend.
+pub fn user_data_path(name: &str) -> Option<std::path::PathBuf> {
+    let mut p = match std::env::var_os("XDG_DATA_HOME") {
+        Some(xdg) if !xdg.is_empty() => std::path::PathBuf::from(xdg),
+        _ => {
+            let mut home = dirs::home_dir()?;
+            home.push(".local/share");
+            home
+        }
+    };
+    p.push("rpi_emulator_frontend");
+    p.push(name);
+    Some(p)
+}
+
+// Expand a leading `~` and `$VAR` / `${VAR}` references in a path from the config. Unset
+// variables expand to nothing (with a warning) so the result is still a usable path. A `$` that
+// doesn't start a reference (alone, `${}`, or `${` without its `}`) is kept as written.
+pub fn expand_path(s: &str) -> String {
+    let mut out = String::new();
+    let mut rest = s;
+    if rest == "~" || rest.starts_with("~/") {
+        if let Some(home) = dirs::home_dir() {
+            out.push_str(&home.to_string_lossy());
+            rest = &rest[1..];
+        }
+    }
+    let mut chars = rest.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '$' {
+            out.push(c);
+            continue;
+        }
+        let mut name = String::new();
+        if chars.peek() == Some(&'{') {
+            let braced: String = chars.clone().skip(1).take_while(|&c| c != '}').collect();
+            let closed = chars.clone().nth(1 + braced.chars().count()) == Some('}');
+            if !closed || braced.is_empty() {
+                // the `{` and the rest go out literally on the next iterations
+                out.push('$');
+                continue;
+            }
+            // skip `{`, the name and `}`
+            for _ in 0..braced.chars().count() + 2 {
+                chars.next();
+            }
+            name = braced;
+        } else {
+            while let Some(&c) = chars.peek() {
+                if !(c.is_ascii_alphanumeric() || c == '_') {
+                    break;
+                }
+                name.push(c);
+                chars.next();
+            }
+        }
+        if name.is_empty() {
+            // a lone `$` is kept as-is
+            out.push('$');
+            continue;
+        }
+        match std::env::var(&name) {
+            Ok(v) => out.push_str(&v),
+            Err(_) => eprintln!("Warning: ${} in config path {} is not set", name, s),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn home() -> String {
+        dirs::home_dir().unwrap().to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn tilde_is_the_home_dir() {
+        assert_eq!(expand_path("~"), home());
+        assert_eq!(expand_path("~/roms"), format!("{}/roms", home()));
+        // only a leading `~` on its own or before `/`
+        assert_eq!(expand_path("~pi/roms"), "~pi/roms");
+        assert_eq!(expand_path("/roms/~"), "/roms/~");
+    }
+
+    #[test]
+    fn variables_are_expanded() {
+        std::env::set_var("RPI_FRONTEND_TEST_ROMS", "/media/usb");
+        assert_eq!(
+            expand_path("$RPI_FRONTEND_TEST_ROMS/snes"),
+            "/media/usb/snes"
+        );
+        assert_eq!(
+            expand_path("${RPI_FRONTEND_TEST_ROMS}_snes"),
+            "/media/usb_snes"
+        );
+    }
+
+    #[test]
+    fn unset_variables_expand_to_nothing() {
+        std::env::remove_var("RPI_FRONTEND_TEST_UNSET");
+        assert_eq!(
+            expand_path("/roms/$RPI_FRONTEND_TEST_UNSET/snes"),
+            "/roms//snes"
+        );
+        assert_eq!(expand_path("/roms/${RPI_FRONTEND_TEST_UNSET}"), "/roms/");
+    }
+
+    #[test]
+    fn a_dollar_that_starts_no_reference_is_kept() {
+        assert_eq!(expand_path("/roms/$"), "/roms/$");
+        assert_eq!(expand_path("/roms/$ snes"), "/roms/$ snes");
+        assert_eq!(expand_path("/roms/${}"), "/roms/${}");
+        assert_eq!(expand_path("${HOME/roms"), "${HOME/roms");
+    }
+}
@@ -0,0 +1,94 @@
+// Recently played ROMs, most recent first, kept in recent.toml in the data dir.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// How many entries are kept.
+const MAX_RECENT: usize = 50;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct RecentEntry {
+    pub system: String,
+    pub rom: PathBuf,
+    // seconds since the Unix epoch
+    pub last_played: u64,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct RecentFile {
+    #[serde(default)]
+    played: Vec<RecentEntry>,
+}
+
+// Load the recent list; missing or unreadable files give an empty list.
+pub fn load() -> Vec<RecentEntry> {
+    crate::paths::user_data_path("recent.toml")
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|s| toml::from_str::<RecentFile>(&s).ok())
+        .map(|f| f.played)
+        .unwrap_or_default()
+}
+
+fn save(entries: &[RecentEntry]) -> Result<(), String> {
+    let p = crate::paths::user_data_path("recent.toml").ok_or("No recent list path available")?;
+    if let Some(parent) = p.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create data dir: {}", e))?;
+    }
+    let file = RecentFile {
+        played: entries.to_vec(),
+    };
+    let s = toml::to_string_pretty(&file)
+        .map_err(|e| format!("Failed to serialize recent list: {}", e))?;
+    let tmp = p.with_extension("toml.tmp");
+    std::fs::write(&tmp, s.as_bytes()).map_err(|e| format!("Failed writing tmp recent: {}", e))?;
+    std::fs::rename(&tmp, &p).map_err(|e| format!("Failed renaming recent: {}", e))?;
+    Ok(())
+}
+
+// Move (or add) `rom` to the front of the list and save it.
+pub fn record(entries: &mut Vec<RecentEntry>, system: &str, rom: &Path) {
+    entries.retain(|e| e.rom != rom);
+    let last_played = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    entries.insert(
+        0,
+        RecentEntry {
+            system: system.to_string(),
+            rom: rom.to_path_buf(),
+            last_played,
+        },
+    );
+    entries.truncate(MAX_RECENT);
+    if let Err(e) = save(entries) {
+        eprintln!("Failed to save recent list: {}", e);
+    }
+}
+
+// ROM path -> when it was last played, for lookups while drawing.
+pub fn played_map(entries: &[RecentEntry]) -> HashMap<PathBuf, u64> {
+    entries
+        .iter()
+        .map(|e| (e.rom.clone(), e.last_played))
+        .collect()
+}
+
+// Short "played 2d ago" style label for a play at `last_played` (Unix seconds).
+pub fn played_ago_label(last_played: u64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    let ago = now.saturating_sub(last_played);
+    if ago < 60 {
+        "played just now".to_string()
+    } else if ago < 3600 {
+        format!("played {}m ago", ago / 60)
+    } else if ago < 86400 {
+        format!("played {}h ago", ago / 3600)
+    } else {
+        format!("played {}d ago", ago / 86400)
+    }
+}
@@ -0,0 +1,77 @@
+// External scraper hook: runs the configured `scraper_program` (e.g. Skyscraper) to fetch
+// metadata and art. The frontend only runs the command and reports progress; it rescans when
+// the scraper is done.
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::mpsc;
+use std::thread;
+
+pub enum ScrapeProgress {
+    // about to run job `done + 1` of `total`
+    Running {
+        done: usize,
+        total: usize,
+        name: String,
+    },
+    Finished {
+        failed: usize,
+        total: usize,
+    },
+}
+
+// Arguments for one scraper run, with `{rom}` and `{system}` substituted anywhere they appear.
+fn build_args(args: &[String], system: &str, rom: Option<&PathBuf>) -> Vec<String> {
+    let rom = rom
+        .map(|r| r.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    args.iter()
+        .map(|a| a.replace("{rom}", &rom).replace("{system}", system))
+        .collect()
+}
+
+// Scrape `roms` of `system` on a background thread. If the args mention `{rom}` the scraper
+// runs once per ROM, otherwise once for the whole system. Progress is sent on `progress`,
+// ending with `Finished`.
+pub fn run_in_background(
+    program: String,
+    args: Vec<String>,
+    system: String,
+    roms: Vec<PathBuf>,
+    progress: mpsc::Sender<ScrapeProgress>,
+) {
+    let jobs: Vec<Option<PathBuf>> = if args.iter().any(|a| a.contains("{rom}")) {
+        roms.into_iter().map(Some).collect()
+    } else {
+        vec![None]
+    };
+    thread::spawn(move || {
+        let total = jobs.len();
+        let mut failed = 0;
+        for (done, rom) in jobs.iter().enumerate() {
+            let name = match rom {
+                Some(r) => r
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .into_owned(),
+                None => system.clone(),
+            };
+            let _ = progress.send(ScrapeProgress::Running { done, total, name });
+            let status = Command::new(&program)
+                .args(build_args(&args, &system, rom.as_ref()))
+                .status();
+            match status {
+                Ok(s) if s.success() => {}
+                Ok(s) => {
+                    eprintln!("{} exited with {}", program, s);
+                    failed += 1;
+                }
+                Err(e) => {
+                    eprintln!("Failed to run {}: {}", program, e);
+                    failed += 1;
+                }
+            }
+        }
+        let _ = progress.send(ScrapeProgress::Finished { failed, total });
+    });
+}
@@ -0,0 +1,400 @@
+// Style (theme) file handling: loading and saving style.toml, built-in presets, and resolving
+// the configured colors into the palette used by the renderer.
+use sdl2::pixels::Color;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct StyleConfig {
+    pub background: Option<[u8; 3]>,
+    pub tile_selected: Option<[u8; 3]>,
+    pub tile_normal: Option<[u8; 3]>,
+    pub text_primary: Option<[u8; 3]>,
+    pub text_secondary: Option<[u8; 3]>,
+    pub banner_bg: Option<[u8; 3]>,
+    pub banner_text: Option<[u8; 3]>,
+    pub emu_text: Option<[u8; 3]>,
+    pub overlay_bg: Option<[u8; 3]>,
+    pub overlay_alpha: Option<u8>,
+    pub menu_bg: Option<[u8; 3]>,
+    pub menu_box: Option<[u8; 3]>,
+    pub menu_selected: Option<[u8; 3]>,
+    pub menu_title: Option<[u8; 3]>,
+    pub menu_text: Option<[u8; 3]>,
+    pub error_overlay_alpha: Option<u8>,
+    pub message_overlay_alpha: Option<u8>,
+    pub max_title_lines: Option<u8>,
+    pub scrollbar_track: Option<[u8; 3]>,
+    pub scrollbar_thumb: Option<[u8; 3]>,
+    pub tile_height: Option<u32>,
+    pub tile_padding: Option<u32>,
+    pub selection_border: Option<u32>,
+    pub selection_pulse: Option<bool>,
+    // pulses per second
+    pub selection_pulse_speed: Option<f32>,
+    pub tile_border: Option<[u8; 3]>,
+    pub tile_border_width: Option<u32>,
+    pub tile_corner_radius: Option<u32>,
+    pub banner_height: Option<u32>,
+    // "center" or "left"
+    pub banner_filename_align: Option<String>,
+}
+
+pub fn user_style_path() -> Option<std::path::PathBuf> {
+    if let Ok(xdg) = std::env::var("XDG_CONFIG_HOME") {
+        let mut p = PathBuf::from(xdg);
+        p.push("rpi_emulator_frontend");
+        p.push("style.toml");
+        Some(p)
+    } else if let Some(home) = dirs::home_dir() {
+        let mut p = home;
+        p.push(".config/rpi_emulator_frontend/style.toml");
+        Some(p)
+    } else {
+        None
+    }
+}
+
+pub fn write_default_style(path: &Path) -> std::io::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // prefer a project-level style.sample.toml if present
+    let sample = if let Ok(s) = std::fs::read_to_string("style.sample.toml") {
+        s
+    } else {
+        include_str!("../style.sample.toml").to_string()
+    };
+    let tmp = path.with_extension("toml.tmp");
+    std::fs::write(&tmp, sample.as_bytes())?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+pub fn load_style() -> crate::Loaded<StyleConfig> {
+    // defaults hard-coded if file missing or parse fails
+    let mut s = StyleConfig {
+        background: Some([12, 12, 12]),
+        tile_selected: Some([200, 180, 50]),
+        tile_normal: Some([60, 60, 60]),
+        text_primary: Some([240, 240, 240]),
+        text_secondary: Some([180, 180, 180]),
+        banner_bg: Some([20, 20, 20]),
+        banner_text: Some([220, 220, 220]),
+        emu_text: Some([180, 180, 180]),
+        overlay_bg: Some([0, 0, 0]),
+        overlay_alpha: Some(200),
+        menu_bg: Some([10, 10, 10]),
+        menu_box: Some([40, 40, 40]),
+        menu_selected: Some([80, 80, 80]),
+        menu_title: Some([230, 230, 230]),
+        menu_text: Some([220, 220, 220]),
+        error_overlay_alpha: Some(200),
+        message_overlay_alpha: Some(160),
+        max_title_lines: Some(2),
+        scrollbar_track: Some([30, 30, 30]),
+        scrollbar_thumb: Some([140, 140, 140]),
+        tile_height: Some(crate::TILE_H as u32),
+        tile_padding: Some(crate::TILE_PADDING as u32),
+        selection_border: Some(0),
+        selection_pulse: Some(false),
+        selection_pulse_speed: Some(1.0),
+        tile_border: Some([100, 100, 100]),
+        tile_border_width: Some(0),
+        tile_corner_radius: Some(0),
+        banner_height: Some(40),
+        banner_filename_align: Some("center".to_string()),
+    };
+
+    let mut created = false;
+    let mut parse_error = None;
+    if let Some(p) = user_style_path() {
+        if !p.exists() {
+            match write_default_style(&p) {
+                Ok(()) => created = true,
+                Err(e) => eprintln!("Failed to write default style: {}", e),
+            }
+        }
+        if let Ok(contents) = std::fs::read_to_string(&p) {
+            let parsed = toml::from_str::<StyleConfig>(&contents);
+            if let Err(e) = &parsed {
+                eprintln!("Failed to parse style at {}: {}", p.display(), e);
+                parse_error = Some(format!(
+                    "Style error, using defaults: {}",
+                    crate::error_summary(&e.to_string())
+                ));
+            }
+            if let Ok(parsed) = parsed {
+                // merge parsed into s
+                if parsed.background.is_some() {
+                    s.background = parsed.background;
+                }
+                if parsed.tile_selected.is_some() {
+                    s.tile_selected = parsed.tile_selected;
+                }
+                if parsed.tile_normal.is_some() {
+                    s.tile_normal = parsed.tile_normal;
+                }
+                if parsed.text_primary.is_some() {
+                    s.text_primary = parsed.text_primary;
+                }
+                if parsed.text_secondary.is_some() {
+                    s.text_secondary = parsed.text_secondary;
+                }
+                if parsed.banner_bg.is_some() {
+                    s.banner_bg = parsed.banner_bg;
+                }
+                if parsed.banner_text.is_some() {
+                    s.banner_text = parsed.banner_text;
+                }
+                if parsed.emu_text.is_some() {
+                    s.emu_text = parsed.emu_text;
+                }
+                if parsed.overlay_bg.is_some() {
+                    s.overlay_bg = parsed.overlay_bg;
+                }
+                if parsed.overlay_alpha.is_some() {
+                    s.overlay_alpha = parsed.overlay_alpha;
+                }
+                if parsed.menu_bg.is_some() {
+                    s.menu_bg = parsed.menu_bg;
+                }
+                if parsed.menu_box.is_some() {
+                    s.menu_box = parsed.menu_box;
+                }
+                if parsed.menu_selected.is_some() {
+                    s.menu_selected = parsed.menu_selected;
+                }
+                if parsed.menu_title.is_some() {
+                    s.menu_title = parsed.menu_title;
+                }
+                if parsed.menu_text.is_some() {
+                    s.menu_text = parsed.menu_text;
+                }
+                if parsed.error_overlay_alpha.is_some() {
+                    s.error_overlay_alpha = parsed.error_overlay_alpha;
+                }
+                if parsed.message_overlay_alpha.is_some() {
+                    s.message_overlay_alpha = parsed.message_overlay_alpha;
+                }
+                if parsed.max_title_lines.is_some() {
+                    s.max_title_lines = parsed.max_title_lines;
+                }
+                if parsed.scrollbar_track.is_some() {
+                    s.scrollbar_track = parsed.scrollbar_track;
+                }
+                if parsed.scrollbar_thumb.is_some() {
+                    s.scrollbar_thumb = parsed.scrollbar_thumb;
+                }
+                if parsed.tile_height.is_some() {
+                    s.tile_height = parsed.tile_height;
+                }
+                if parsed.tile_padding.is_some() {
+                    s.tile_padding = parsed.tile_padding;
+                }
+                if parsed.selection_border.is_some() {
+                    s.selection_border = parsed.selection_border;
+                }
+                if parsed.selection_pulse.is_some() {
+                    s.selection_pulse = parsed.selection_pulse;
+                }
+                if parsed.selection_pulse_speed.is_some() {
+                    s.selection_pulse_speed = parsed.selection_pulse_speed;
+                }
+                if parsed.tile_border.is_some() {
+                    s.tile_border = parsed.tile_border;
+                }
+                if parsed.tile_border_width.is_some() {
+                    s.tile_border_width = parsed.tile_border_width;
+                }
+                if parsed.tile_corner_radius.is_some() {
+                    s.tile_corner_radius = parsed.tile_corner_radius;
+                }
+                if parsed.banner_height.is_some() {
+                    s.banner_height = parsed.banner_height;
+                }
+                if parsed.banner_filename_align.is_some() {
+                    s.banner_filename_align = parsed.banner_filename_align;
+                }
+            }
+        }
+    }
+
+    crate::Loaded {
+        value: s,
+        created,
+        parse_error,
+    }
+}
+
+pub fn write_style(style: &StyleConfig) -> Result<(), String> {
+    if let Some(p) = user_style_path() {
+        if let Some(parent) = p.parent() {
+            if let Err(e) = std::fs::create_dir_all(parent) {
+                return Err(format!("Failed to create config dir: {}", e));
+            }
+        }
+        match toml::to_string_pretty(style) {
+            Ok(s) => {
+                let tmp = p.with_extension("toml.tmp");
+                if let Err(e) = std::fs::write(&tmp, s.as_bytes()) {
+                    return Err(format!("Failed writing tmp style: {}", e));
+                }
+                if let Err(e) = std::fs::rename(&tmp, &p) {
+                    return Err(format!("Failed renaming style: {}", e));
+                }
+                return Ok(());
+            }
+            Err(e) => return Err(format!("Failed to serialize style: {}", e)),
+        }
+    }
+    Err("No style path available".into())
+}
+
+// Built-in themes, cycled from the settings menu.
+pub const PRESETS: [&str; 3] = ["dark", "light", "high-contrast"];
+
+// Overwrite the colors in `s` with the named preset. Layout fields (tile size, title lines) are
+// left alone. Returns false for an unknown preset name.
+pub fn apply_preset(s: &mut StyleConfig, name: &str) -> bool {
+    // background, tile_selected, tile_normal, text_primary, text_secondary, banner_bg,
+    // banner_text, emu_text, menu_bg, menu_box, menu_selected, menu_title, menu_text,
+    // scrollbar_track, scrollbar_thumb
+    let c: [[u8; 3]; 15] = match name {
+        "dark" => [
+            [12, 12, 12],
+            [200, 180, 50],
+            [60, 60, 60],
+            [240, 240, 240],
+            [180, 180, 180],
+            [20, 20, 20],
+            [220, 220, 220],
+            [180, 180, 180],
+            [10, 10, 10],
+            [40, 40, 40],
+            [80, 80, 80],
+            [230, 230, 230],
+            [220, 220, 220],
+            [30, 30, 30],
+            [140, 140, 140],
+        ],
+        "light" => [
+            [235, 235, 230],
+            [90, 140, 220],
+            [200, 200, 195],
+            [20, 20, 20],
+            [70, 70, 70],
+            [210, 210, 205],
+            [30, 30, 30],
+            [80, 80, 80],
+            [240, 240, 235],
+            [215, 215, 210],
+            [170, 190, 225],
+            [20, 20, 20],
+            [30, 30, 30],
+            [210, 210, 205],
+            [110, 110, 110],
+        ],
+        "high-contrast" => [
+            [0, 0, 0],
+            [255, 255, 0],
+            [0, 0, 0],
+            [255, 255, 255],
+            [255, 255, 255],
+            [0, 0, 0],
+            [255, 255, 0],
+            [255, 255, 255],
+            [0, 0, 0],
+            [0, 0, 0],
+            [0, 0, 160],
+            [255, 255, 0],
+            [255, 255, 255],
+            [60, 60, 60],
+            [255, 255, 0],
+        ],
+        _ => return false,
+    };
+    s.background = Some(c[0]);
+    s.tile_selected = Some(c[1]);
+    s.tile_normal = Some(c[2]);
+    s.text_primary = Some(c[3]);
+    s.text_secondary = Some(c[4]);
+    s.banner_bg = Some(c[5]);
+    s.banner_text = Some(c[6]);
+    s.emu_text = Some(c[7]);
+    s.menu_bg = Some(c[8]);
+    s.menu_box = Some(c[9]);
+    s.menu_selected = Some(c[10]);
+    s.menu_title = Some(c[11]);
+    s.menu_text = Some(c[12]);
+    s.scrollbar_track = Some(c[13]);
+    s.scrollbar_thumb = Some(c[14]);
+    true
+}
+
+// Accessibility overrides: high-contrast colors, taller tiles and a thick outline around the
+// selected tile. Applied on top of the loaded style, so it wins over individual fields.
+pub fn apply_accessibility(s: &mut StyleConfig) {
+    apply_preset(s, "high-contrast");
+    s.tile_height = Some(s.tile_height.unwrap_or(0).max(200));
+    s.selection_border = Some(s.selection_border.unwrap_or(0).max(6));
+}
+
+// Colors resolved from a StyleConfig, ready for drawing.
+#[derive(Clone, Copy)]
+pub struct Palette {
+    pub bg_color: Color,
+    pub tile_selected_c: Color,
+    pub tile_normal_c: Color,
+    pub text_primary_c: Color,
+    pub text_secondary_c: Color,
+    pub banner_bg_c: Color,
+    pub banner_text_c: Color,
+    pub emu_text_c: Color,
+    pub overlay_rgba: Color,
+    pub menu_bg_c: Color,
+    pub menu_box_c: Color,
+    pub menu_selected_c: Color,
+    pub menu_title_c: Color,
+    pub menu_text_c: Color,
+    pub message_overlay_rgba: Color,
+    pub scrollbar_track_c: Color,
+    pub scrollbar_thumb_c: Color,
+    pub tile_border_c: Color,
+}
+
+impl Palette {
+    pub fn from_style(style: &StyleConfig) -> Palette {
+        let to_rgb = |arr: [u8; 3]| -> Color { Color::RGB(arr[0], arr[1], arr[2]) };
+        let to_rgba = |arr: [u8; 3], a: u8| -> Color { Color::RGBA(arr[0], arr[1], arr[2], a) };
+        let overlay_base = style.overlay_bg.unwrap_or([0, 0, 0]);
+        let overlay_alpha = style.overlay_alpha.unwrap_or(200);
+        Palette {
+            bg_color: to_rgb(style.background.unwrap_or([12, 12, 12])),
+            tile_selected_c: to_rgb(style.tile_selected.unwrap_or([200, 180, 50])),
+            tile_normal_c: to_rgb(style.tile_normal.unwrap_or([60, 60, 60])),
+            text_primary_c: to_rgb(style.text_primary.unwrap_or([240, 240, 240])),
+            text_secondary_c: to_rgb(style.text_secondary.unwrap_or([180, 180, 180])),
+            banner_bg_c: to_rgb(style.banner_bg.unwrap_or([20, 20, 20])),
+            banner_text_c: to_rgb(style.banner_text.unwrap_or([220, 220, 220])),
+            emu_text_c: to_rgb(style.emu_text.unwrap_or([180, 180, 180])),
+            overlay_rgba: to_rgba(overlay_base, overlay_alpha),
+            menu_bg_c: to_rgb(style.menu_bg.unwrap_or([10, 10, 10])),
+            menu_box_c: to_rgb(style.menu_box.unwrap_or([40, 40, 40])),
+            menu_selected_c: to_rgb(style.menu_selected.unwrap_or([80, 80, 80])),
+            menu_title_c: to_rgb(style.menu_title.unwrap_or([230, 230, 230])),
+            menu_text_c: to_rgb(style.menu_text.unwrap_or([220, 220, 220])),
+            message_overlay_rgba: to_rgba(overlay_base, style.message_overlay_alpha.unwrap_or(160)),
+            scrollbar_track_c: to_rgb(style.scrollbar_track.unwrap_or([30, 30, 30])),
+            scrollbar_thumb_c: to_rgb(style.scrollbar_thumb.unwrap_or([140, 140, 140])),
+            tile_border_c: to_rgb(style.tile_border.unwrap_or([100, 100, 100])),
+        }
+    }
+}
+
+// Mix `from` toward `to`; `t` = 0 gives `from`, 1 gives `to`.
+pub fn blend(from: Color, to: Color, t: f32) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    let mix = |a: u8, b: u8| -> u8 { (a as f32 + (b as f32 - a as f32) * t).round() as u8 };
+    Color::RGB(mix(from.r, to.r), mix(from.g, to.g), mix(from.b, to.b))
+}
@@ -0,0 +1,288 @@
+// Text layout helpers shared by the renderers. Widths are measured through a caller-supplied
+// function (normally the TTF font) so the logic can be tested without SDL.
+
+// Break `s` into at most `max_lines` lines no wider than `max_w` (as measured by `width_of`).
+// Lines prefer to break after a separator; the last line is truncated with an ellipsis.
+pub fn wrap_to_lines(
+    s: &str,
+    max_w: u32,
+    max_lines: usize,
+    width_of: impl Fn(&str) -> u32,
+) -> Vec<String> {
+    let seps = [' ', '-', ':', '_'];
+    let mut lines: Vec<String> = Vec::new();
+    let mut rest = s.to_string();
+    while lines.len() + 1 < max_lines.max(1) && width_of(&rest) > max_w {
+        // find maximal prefix that fits on this line (binary search)
+        let chars: Vec<char> = rest.chars().collect();
+        let mut lo = 0usize;
+        let mut hi = chars.len();
+        while lo < hi {
+            let mid = (lo + hi).div_ceil(2);
+            let cand: String = chars[..mid].iter().collect();
+            if width_of(&cand) <= max_w {
+                lo = mid;
+            } else {
+                hi = mid - 1;
+            }
+        }
+        // smart-split at the last separator within the prefix (dropping the separator)
+        let (take, skip) = match chars[..lo].iter().rposition(|c| seps.contains(c)) {
+            Some(pos) if pos > 0 => (pos, pos + 1),
+            _ => (lo, lo),
+        };
+        if take == 0 {
+            break;
+        }
+        lines.push(chars[..take].iter().collect());
+        rest = chars[skip..].iter().collect();
+    }
+    lines.push(truncate_with_ellipsis(&rest, max_w, &width_of));
+    lines
+}
+
+// Return `s` unchanged if it fits in `max_w`, else its longest prefix that fits with "..." added.
+fn truncate_with_ellipsis(s: &str, max_w: u32, width_of: &impl Fn(&str) -> u32) -> String {
+    if width_of(s) <= max_w {
+        return s.to_string();
+    }
+    let ell = "...";
+    let chars: Vec<char> = s.chars().collect();
+    let mut lo = 0usize;
+    let mut hi = chars.len();
+    while lo < hi {
+        let mid = (lo + hi).div_ceil(2);
+        let cand: String = chars[..mid].iter().collect::<String>() + ell;
+        if width_of(&cand) <= max_w {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    chars[..lo].iter().collect::<String>() + ell
+}
+
+// Shorten `s` to at most `max_chars` characters by replacing its middle with "...".
+pub fn elide_middle(s: &str, max_chars: usize) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    if chars.len() <= max_chars {
+        return s.to_string();
+    }
+    if max_chars <= 3 {
+        return "...".to_string();
+    }
+    let keep = (max_chars - 3) / 2;
+    let head = keep + ((max_chars - 3) % 2);
+    let tail = keep;
+    let start: String = chars.iter().take(head).collect();
+    let end: String = chars
+        .iter()
+        .rev()
+        .take(tail)
+        .collect::<Vec<&char>>()
+        .into_iter()
+        .rev()
+        .collect();
+    format!("{}...{}", start, end)
+}
+
+// Middle-elide `s` (as elide_middle does) to as many characters as fit in `max_w`.
+pub fn elide_middle_to_width(s: &str, max_w: u32, width_of: impl Fn(&str) -> u32) -> String {
+    if width_of(s) <= max_w {
+        return s.to_string();
+    }
+    // `lo` characters fit (0 always counts: it gives just "..."), `hi` don't
+    let (mut lo, mut hi) = (0usize, s.chars().count());
+    while lo + 1 < hi {
+        let mid = (lo + hi) / 2;
+        if width_of(&elide_middle(s, mid)) <= max_w {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    elide_middle(s, lo)
+}
+
+// Compare names the way people expect numbered titles to sort: runs of digits compare by
+// value ("Game 2" < "Game 10"), other text compares case-insensitively. Names that only differ
+// in case or leading zeros fall back to a plain comparison so the order is total.
+pub fn natural_cmp(a: &str, b: &str) -> std::cmp::Ordering {
+    use std::cmp::Ordering;
+    let (mut x, mut y) = (a, b);
+    loop {
+        let (cx, cy) = match (x.chars().next(), y.chars().next()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(cx), Some(cy)) => (cx, cy),
+        };
+        let ord = if cx.is_ascii_digit() && cy.is_ascii_digit() {
+            let nx = x.len() - x.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let ny = y.len() - y.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+            let (dx, dy) = (
+                x[..nx].trim_start_matches('0'),
+                y[..ny].trim_start_matches('0'),
+            );
+            x = &x[nx..];
+            y = &y[ny..];
+            // no leading zeros: the longer number is bigger, equal lengths compare digit-wise
+            dx.len().cmp(&dy.len()).then_with(|| dx.cmp(dy))
+        } else {
+            x = &x[cx.len_utf8()..];
+            y = &y[cy.len_utf8()..];
+            cx.to_lowercase().cmp(cy.to_lowercase())
+        };
+        if ord != Ordering::Equal {
+            return ord;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every character is 10px wide
+    fn width(s: &str) -> u32 {
+        s.chars().count() as u32 * 10
+    }
+
+    #[test]
+    fn short_text_stays_on_one_line() {
+        assert_eq!(wrap_to_lines("Tetris", 100, 2, width), vec!["Tetris"]);
+    }
+
+    #[test]
+    fn wraps_at_last_separator() {
+        assert_eq!(
+            wrap_to_lines("Super Mario Kart", 100, 2, width),
+            vec!["Super", "Mario Kart"]
+        );
+    }
+
+    #[test]
+    fn splits_mid_word_without_separator() {
+        assert_eq!(
+            wrap_to_lines("ABCDEFGHIJKLMNO", 100, 2, width),
+            vec!["ABCDEFGHIJ", "KLMNO"]
+        );
+    }
+
+    #[test]
+    fn only_last_line_is_truncated() {
+        assert_eq!(
+            wrap_to_lines("aaaa bbbb cccc dddd eeee ffff", 100, 3, width),
+            vec!["aaaa bbbb", "cccc dddd", "eeee ffff"]
+        );
+        assert_eq!(
+            wrap_to_lines("aaaa bbbb cccc dddd eeee ffff gggg", 100, 2, width),
+            vec!["aaaa bbbb", "cccc dd..."]
+        );
+    }
+
+    #[test]
+    fn single_line_truncates() {
+        assert_eq!(
+            wrap_to_lines("Super Mario World", 100, 1, width),
+            vec!["Super M..."]
+        );
+    }
+
+    #[test]
+    fn zero_lines_is_treated_as_one() {
+        assert_eq!(wrap_to_lines("Tetris", 100, 0, width), vec!["Tetris"]);
+    }
+
+    #[test]
+    fn nothing_fits_gives_ellipsis() {
+        assert_eq!(wrap_to_lines("Tetris", 5, 2, width), vec!["..."]);
+    }
+
+    #[test]
+    fn elide_middle_keeps_both_ends() {
+        assert_eq!(elide_middle("abcdefghij", 10), "abcdefghij");
+        assert_eq!(elide_middle("abcdefghijkl", 9), "abc...jkl");
+        assert_eq!(elide_middle("abcdefghijkl", 8), "abc...kl");
+        assert_eq!(elide_middle("abcdefghijkl", 3), "...");
+    }
+
+    fn natural_sorted(names: &[&str]) -> Vec<String> {
+        let mut v: Vec<String> = names.iter().map(|s| s.to_string()).collect();
+        v.sort_by(|a, b| natural_cmp(a, b));
+        v
+    }
+
+    #[test]
+    fn numbers_sort_by_value() {
+        assert_eq!(
+            natural_sorted(&["Game 10.gba", "Game 2.gba", "Game 1.gba"]),
+            vec!["Game 1.gba", "Game 2.gba", "Game 10.gba"]
+        );
+    }
+
+    #[test]
+    fn mixed_names_and_numbers() {
+        assert_eq!(
+            natural_sorted(&[
+                "Mega Man 10.nes",
+                "Mega Man.nes",
+                "mega man 3.nes",
+                "Mega Man 2.nes",
+                "Contra.nes",
+                "1942.nes",
+                "007 Racing.bin",
+            ]),
+            vec![
+                "007 Racing.bin",
+                "1942.nes",
+                "Contra.nes",
+                "Mega Man 2.nes",
+                "mega man 3.nes",
+                "Mega Man 10.nes",
+                "Mega Man.nes",
+            ]
+        );
+    }
+
+    #[test]
+    fn leading_zeros_and_case_still_give_a_total_order() {
+        assert_eq!(
+            natural_sorted(&["Disc 01", "Disc 1", "disc 1"]),
+            vec!["Disc 01", "Disc 1", "disc 1"]
+        );
+        assert_eq!(natural_cmp("Disc 1", "Disc 1"), std::cmp::Ordering::Equal);
+    }
+
+    #[test]
+    fn long_numbers_do_not_overflow() {
+        assert_eq!(
+            natural_sorted(&["x 99999999999999999999999", "x 100000000000000000000000"]),
+            vec!["x 99999999999999999999999", "x 100000000000000000000000"]
+        );
+    }
+
+    #[test]
+    fn elide_to_width_leaves_fitting_text_alone() {
+        assert_eq!(elide_middle_to_width("Tetris.gb", 90, width), "Tetris.gb");
+    }
+
+    #[test]
+    fn elide_to_width_keeps_both_ends() {
+        let out = elide_middle_to_width("Super Mario World.sfc", 110, width);
+        assert_eq!(out, "Supe....sfc");
+        assert!(width(&out) <= 110);
+    }
+
+    #[test]
+    fn elide_to_width_gives_dots_when_nothing_fits() {
+        assert_eq!(
+            elide_middle_to_width("Super Mario World.sfc", 5, width),
+            "..."
+        );
+        assert_eq!(
+            elide_middle_to_width("Super Mario World.sfc", 0, width),
+            "..."
+        );
+    }
+}
@@ -0,0 +1,40 @@
+// System volume through ALSA's `amixer`, for the optional on-screen volume control.
+use std::process::Command;
+
+// Run `amixer` with `args` and return the control's volume in percent from its output.
+fn amixer(args: &[&str]) -> Result<u8, String> {
+    let out = Command::new("amixer")
+        .args(args)
+        .output()
+        .map_err(|e| format!("amixer not available: {}", e))?;
+    if !out.status.success() {
+        let err = String::from_utf8_lossy(&out.stderr);
+        return Err(err
+            .lines()
+            .next()
+            .unwrap_or("amixer failed")
+            .trim()
+            .to_string());
+    }
+    parse_percent(&String::from_utf8_lossy(&out.stdout))
+        .ok_or_else(|| "amixer reported no volume".to_string())
+}
+
+// First "[NN%]" in amixer's control listing.
+fn parse_percent(output: &str) -> Option<u8> {
+    output.split('[').skip(1).find_map(|part| {
+        let pct = part.split(']').next()?.strip_suffix('%')?;
+        pct.parse::<u8>().ok()
+    })
+}
+
+// Raise (positive `delta`) or lower the volume of `control` (e.g. "Master", "PCM") by `delta`
+// percent. Returns the new volume.
+pub fn change(control: &str, delta: i8) -> Result<u8, String> {
+    let step = format!(
+        "{}%{}",
+        delta.unsigned_abs(),
+        if delta < 0 { '-' } else { '+' }
+    );
+    amixer(&["-M", "set", control, &step])
+}